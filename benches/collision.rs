@@ -0,0 +1,52 @@
+use bevy::prelude::{Mat4, Quat, Vec3};
+use bevy_collision::{
+    broad_phase::colliding_pairs,
+    collider::{Collider, ColliderShapes},
+    layers::CollisionFilter,
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+use rand::{prelude::SmallRng, Rng, SeedableRng};
+
+/// voxel sized cubes and spheres, the area grows with the count so the density stays the same
+fn scattered_colliders(count: usize) -> (Vec<Collider>, Vec<Mat4>) {
+    let mut rng = SmallRng::seed_from_u64(42);
+    let extent = (count as f32).sqrt() * 2.0;
+    let colliders = (0..count)
+        .map(|i| Collider {
+            collider_shape: if i % 4 == 0 {
+                ColliderShapes::Sphere { radius: 0.5 }
+            } else {
+                ColliderShapes::cube(0.9)
+            },
+            local_position: Vec3::ZERO,
+            filter: CollisionFilter::default(),
+            sensor: false,
+        })
+        .collect();
+    let transforms = (0..count)
+        .map(|_| {
+            Mat4::from_rotation_translation(
+                Quat::from_rotation_y(rng.gen_range(0.0..std::f32::consts::PI)),
+                Vec3::new(
+                    rng.gen_range(0.0..extent),
+                    rng.gen_range(0.0..4.0),
+                    rng.gen_range(0.0..extent),
+                ),
+            )
+        })
+        .collect();
+    (colliders, transforms)
+}
+
+fn collision_benchmark(c: &mut Criterion) {
+    for count in [1000, 2000, 4000, 8000].iter() {
+        let (colliders, transforms) = scattered_colliders(*count);
+        let shapes: Vec<(&Collider, Mat4)> = colliders.iter().zip(transforms).collect();
+        c.bench_function(&format!("colliding pairs {}", count), |b| {
+            b.iter(|| colliding_pairs(&shapes))
+        });
+    }
+}
+
+criterion_group!(benches, collision_benchmark);
+criterion_main!(benches);
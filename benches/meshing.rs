@@ -0,0 +1,47 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use voxel::{
+    boundaries::{ChunkBoundaries, CHUNK_SIZE},
+    chunk::VoxelChunk,
+    chunk_mesh::ChunkMesher,
+    neighbours::ChunkNeighbours,
+    voxel::{Voxel, VoxelPosition, VoxelTypes},
+};
+
+fn terrain_chunk(lod: i32) -> VoxelChunk {
+    let mut chunk = VoxelChunk::empty(ChunkBoundaries::aligned(VoxelPosition::new(0, 0, 0)));
+    chunk.lod = lod;
+    for x in 0..CHUNK_SIZE {
+        for z in 0..CHUNK_SIZE {
+            let height = 20 + ((x as f32 / 10.0).sin() * 5.0 + (z as f32 / 7.0).cos() * 3.0) as i32;
+            for y in 0..height {
+                let typ = if y == height - 1 {
+                    VoxelTypes::Moss
+                } else {
+                    VoxelTypes::GreyRock1
+                };
+                chunk.set(Voxel::new(x, y, z, typ));
+            }
+        }
+    }
+    chunk
+}
+
+fn meshing_benchmark(c: &mut Criterion) {
+    for lod in [1, 4].iter() {
+        let chunk = terrain_chunk(*lod);
+        let neighbours = ChunkNeighbours::none(chunk.boundary);
+        for (name, mesher) in [
+            ("faces", ChunkMesher::Faces),
+            ("greedy", ChunkMesher::Greedy),
+        ]
+        .iter()
+        {
+            c.bench_function(&format!("{} lod {}", name, lod), |b| {
+                b.iter(|| mesher.mesh(&chunk, &neighbours))
+            });
+        }
+    }
+}
+
+criterion_group!(benches, meshing_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,297 @@
+use bevy::prelude::*;
+
+use crate::{collider::Collider, shapes::ConvexPart};
+
+/// axis aligned bounding box in world space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.min.is_finite() && self.max.is_finite()
+    }
+}
+
+impl Collider {
+    pub fn aabb(&self, transform: &Mat4) -> Aabb {
+        aabb_of_parts(&self.convex_parts(transform))
+    }
+}
+
+fn aabb_of_parts(parts: &[ConvexPart]) -> Aabb {
+    let aabbs: Vec<Aabb> = parts.iter().map(ConvexPart::aabb).collect();
+    Aabb {
+        min: aabbs
+            .iter()
+            .fold(Vec3::splat(f32::INFINITY), |min, aabb| min.min(aabb.min)),
+        max: aabbs
+            .iter()
+            .fold(Vec3::splat(f32::NEG_INFINITY), |max, aabb| {
+                max.max(aabb.max)
+            }),
+    }
+}
+
+/*
+Sweep and prune along the x axis: the boxes are sorted by their min x, while sweeping only the boxes whose x range
+still reaches the current box are kept as candidates. Returns the pairs of overlapping boxes as (i, j) with i < j, sorted.
+Boxes that are not finite, e.g. of colliders with a NaN position, overlap nothing.
+ */
+pub fn overlapping_pairs(aabbs: &[Aabb]) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..aabbs.len()).filter(|i| aabbs[*i].is_finite()).collect();
+    order.sort_unstable_by(|a, b| aabbs[*a].min.x.partial_cmp(&aabbs[*b].min.x).unwrap());
+
+    let mut pairs = Vec::new();
+    let mut active: Vec<usize> = Vec::new();
+    for i in order {
+        let current = &aabbs[i];
+        active.retain(|a| aabbs[*a].max.x >= current.min.x);
+        for a in active.iter() {
+            if aabbs[*a].overlaps(current) {
+                pairs.push(((*a).min(i), (*a).max(i)));
+            }
+        }
+        active.push(i);
+    }
+    pairs.sort_unstable();
+    pairs
+}
+
+/// collision of two colliders found by colliding_pairs, first < second
+#[derive(Clone, Copy, Debug)]
+pub struct PairCollision {
+    pub first: usize,
+    pub second: usize,
+    /// first has to be moved by -penetration to resolve the collision
+    pub penetration: Vec3,
+    /// halfway between the deepest points of both colliders
+    pub point: Vec3,
+}
+
+/// Runs the narrow phase on the pairs found by the broad phase whose collision filters accept each other.
+pub fn colliding_pairs(colliders: &[(&Collider, Mat4)]) -> Vec<PairCollision> {
+    let parts: Vec<Vec<ConvexPart>> = colliders
+        .iter()
+        .map(|(collider, transform)| collider.convex_parts(transform))
+        .collect();
+    let aabbs: Vec<Aabb> = parts.iter().map(|parts| aabb_of_parts(parts)).collect();
+
+    overlapping_pairs(&aabbs)
+        .into_iter()
+        .filter(|(i, j)| {
+            colliders[*i]
+                .0
+                .filter
+                .collides_with(&colliders[*j].0.filter)
+        })
+        .filter_map(|(i, j)| {
+            let (collider, transform) = &colliders[i];
+            let (other, other_transform) = &colliders[j];
+            collider
+                .detect_collision_with_parts(
+                    other,
+                    transform,
+                    other_transform,
+                    &parts[i],
+                    &parts[j],
+                )
+                .filter(|penetration| *penetration != Vec3::ZERO)
+                .map(|penetration| {
+                    let deepest = support_point(&parts[i], penetration);
+                    PairCollision {
+                        first: i,
+                        second: j,
+                        penetration,
+                        point: deepest - penetration * 0.5,
+                    }
+                })
+        })
+        .collect()
+}
+
+impl Collider {
+    /// point of the collider reaching furthest in direction, the center of the face or edge if several do
+    pub fn support_point(&self, transform: &Mat4, direction: Vec3) -> Vec3 {
+        support_point(&self.convex_parts(transform), direction)
+    }
+}
+
+fn support_point(parts: &[ConvexPart], direction: Vec3) -> Vec3 {
+    parts
+        .iter()
+        .map(|part| part.support_point(direction))
+        .fold((f32::NEG_INFINITY, Vec3::ZERO), |furthest, support| {
+            if support.0 > furthest.0 {
+                support
+            } else {
+                furthest
+            }
+        })
+        .1
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{Mat4, Vec3};
+    use rand::{prelude::SmallRng, Rng, SeedableRng};
+
+    use crate::{
+        collider::{Collider, ColliderShapes},
+        layers::{CollisionFilter, CollisionLayers},
+    };
+
+    use super::{colliding_pairs, overlapping_pairs, Aabb};
+
+    #[test]
+    fn sweep_and_prune_finds_the_same_pairs_as_brute_force() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let aabbs: Vec<Aabb> = (0..300)
+            .map(|_| {
+                let min = Vec3::new(
+                    rng.gen_range(0.0..50.0),
+                    rng.gen_range(0.0..50.0),
+                    rng.gen_range(0.0..50.0),
+                );
+                Aabb {
+                    min,
+                    max: min + Vec3::splat(rng.gen_range(0.5..4.0)),
+                }
+            })
+            .collect();
+
+        let mut brute_force = Vec::new();
+        for i in 0..aabbs.len() {
+            for j in i + 1..aabbs.len() {
+                if aabbs[i].overlaps(&aabbs[j]) {
+                    brute_force.push((i, j));
+                }
+            }
+        }
+
+        assert!(!brute_force.is_empty());
+        assert_eq!(overlapping_pairs(&aabbs), brute_force);
+    }
+
+    #[test]
+    fn boxes_that_are_not_finite_overlap_nothing() {
+        let unit = Aabb {
+            min: Vec3::ZERO,
+            max: Vec3::ONE,
+        };
+        let aabbs = vec![
+            unit,
+            Aabb {
+                min: Vec3::new(f32::NAN, 0.0, 0.0),
+                max: Vec3::ONE,
+            },
+            Aabb {
+                min: Vec3::splat(f32::NEG_INFINITY),
+                max: Vec3::splat(f32::INFINITY),
+            },
+            unit,
+        ];
+
+        assert_eq!(overlapping_pairs(&aabbs), vec![(0, 3)]);
+    }
+
+    #[test]
+    fn only_touching_colliders_collide() {
+        let sphere = Collider {
+            collider_shape: ColliderShapes::Sphere { radius: 1.0 },
+            local_position: Vec3::ZERO,
+            filter: CollisionFilter::default(),
+            sensor: false,
+        };
+        let colliders = vec![
+            (&sphere, Mat4::from_translation(Vec3::new(0.0, 0.0, 0.0))),
+            (&sphere, Mat4::from_translation(Vec3::new(10.0, 0.0, 0.0))),
+            (&sphere, Mat4::from_translation(Vec3::new(1.5, 0.0, 0.0))),
+        ];
+
+        let pairs = colliding_pairs(&colliders);
+
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].first, pairs[0].second), (0, 2));
+        assert!(pairs[0].penetration.x > 0.0);
+        assert!((pairs[0].point - Vec3::new(0.75, 0.0, 0.0)).length() < 0.001);
+    }
+
+    #[test]
+    fn filtered_colliders_do_not_collide() {
+        let debris = Collider {
+            collider_shape: ColliderShapes::cube(1.0),
+            local_position: Vec3::ZERO,
+            filter: CollisionFilter::new(
+                CollisionLayers::DEBRIS,
+                CollisionLayers::ALL.without(CollisionLayers::PICKUP),
+            ),
+            sensor: false,
+        };
+        let pickup = Collider {
+            collider_shape: ColliderShapes::Sphere { radius: 0.5 },
+            local_position: Vec3::ZERO,
+            filter: CollisionFilter::new(CollisionLayers::PICKUP, CollisionLayers::ALL),
+            sensor: false,
+        };
+        let colliders = vec![
+            (&debris, Mat4::IDENTITY),
+            (&pickup, Mat4::from_translation(Vec3::new(0.5, 0.0, 0.0))),
+            (&debris, Mat4::from_translation(Vec3::new(0.0, 0.5, 0.0))),
+        ];
+
+        let pairs = colliding_pairs(&colliders);
+
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].first, pairs[0].second), (0, 2));
+    }
+
+    #[test]
+    fn capsules_and_compounds_collide_through_their_parts() {
+        let collider = |collider_shape| Collider {
+            collider_shape,
+            local_position: Vec3::ZERO,
+            filter: CollisionFilter::default(),
+            sensor: false,
+        };
+        let capsule = collider(ColliderShapes::Capsule {
+            half_height: 1.0,
+            radius: 0.5,
+        });
+        let cube = collider(ColliderShapes::cube(1.0));
+        let compound = collider(ColliderShapes::Compound {
+            children: vec![
+                (Mat4::IDENTITY, ColliderShapes::cube(1.0)),
+                (
+                    Mat4::from_translation(Vec3::new(0.0, 10.0, 0.0)),
+                    ColliderShapes::cube(1.0),
+                ),
+            ],
+        });
+        let colliders = vec![
+            (&capsule, Mat4::IDENTITY),
+            (&cube, Mat4::from_translation(Vec3::new(0.8, 0.0, 0.0))),
+            (&compound, Mat4::from_translation(Vec3::new(20.0, 0.0, 0.0))),
+            (&cube, Mat4::from_translation(Vec3::new(20.0, 10.8, 0.0))),
+        ];
+
+        let pairs = colliding_pairs(&colliders);
+
+        assert_eq!(pairs.len(), 2);
+        assert_eq!((pairs[0].first, pairs[0].second), (0, 1));
+        assert!(pairs[0].penetration.x > 0.0);
+        assert_eq!((pairs[1].first, pairs[1].second), (2, 3));
+        assert!(pairs[1].penetration.y > 0.0);
+    }
+}
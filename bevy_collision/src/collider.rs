@@ -2,9 +2,8 @@ use bevy::prelude::*;
 use cgmath::num_traits::Float;
 
 use bevy::math::Vec4Swizzles;
-use itertools::iproduct;
-use std::collections::HashMap;
-use std::ops::AddAssign;
+
+use crate::{contacts::Contacts, layers::CollisionFilter, shapes::ConvexPart};
 
 pub enum ColliderShapes {
     Sphere {
@@ -15,6 +14,19 @@ pub enum ColliderShapes {
         half_height_y: f32,
         half_depth_z: f32,
     },
+    /// segment along the local y axis, rounded by radius
+    Capsule {
+        half_height: f32,
+        radius: f32,
+    },
+    /// vertices in the local space of the collider, see convex_hull to build one from a mesh
+    ConvexHull {
+        vertices: Vec<Vec3>,
+    },
+    /// child shapes with transforms relative to the collider
+    Compound {
+        children: Vec<(Mat4, ColliderShapes)>,
+    },
 }
 
 impl ColliderShapes {
@@ -32,63 +44,110 @@ impl ColliderShapes {
 pub struct Collider {
     pub collider_shape: ColliderShapes,
     pub local_position: Vec3,
+    pub filter: CollisionFilter,
+    /// sensors report overlapping colliders instead of pushing them out, they ignore the terrain
+    pub sensor: bool,
 }
 
 impl Collider {
-    fn detect_collision(
+    /// penetration of self into other, parts are the convex_parts of both colliders
+    pub(crate) fn detect_collision_with_parts(
         &self,
         other: &Collider,
         transform: &Mat4,
         other_transform: &Mat4,
+        parts: &[ConvexPart],
+        other_parts: &[ConvexPart],
     ) -> Option<Vec3> {
-        match self.collider_shape {
-            ColliderShapes::Sphere { radius } => {
-                Collider::collision_sphere(self, radius, other, transform, other_transform)
-            }
-            ColliderShapes::Cuboid {
-                half_width_x,
-                half_height_y,
-                half_depth_z,
-            } => Collider::collision_cuboid(
-                self,
-                half_width_x,
-                half_height_y,
-                half_depth_z,
-                other,
-                transform,
-                other_transform,
-            ),
-        }
+        self.analytic_collision(other, transform, other_transform)
+            .unwrap_or_else(|| Collider::collision_convex(parts, other_parts))
     }
 
-    fn collision_sphere(
+    /// the collider in world space, compound colliders consist of several parts
+    pub fn convex_parts(&self, transform: &Mat4) -> Vec<ConvexPart> {
+        let mut parts = Vec::new();
+        self.collider_shape.convex_parts(
+            &(*transform * Mat4::from_translation(self.local_position)),
+            &mut parts,
+        );
+        parts
+    }
+
+    /// spheres are solved directly against spheres and cuboids, None if the shapes need GJK
+    fn analytic_collision(
         &self,
-        self_radius: f32,
         other: &Collider,
         transform: &Mat4,
         other_transform: &Mat4,
-    ) -> Option<Vec3> {
-        let self_to_other =
-            Collider::midpoint_to_other_midpoint(&self, transform, other, other_transform);
-        match other.collider_shape {
-            ColliderShapes::Sphere { radius } => {
-                Collider::collision_sphere_sphere(self_to_other, radius, self_radius)
+    ) -> Option<Option<Vec3>> {
+        match (&self.collider_shape, &other.collider_shape) {
+            (
+                ColliderShapes::Sphere {
+                    radius: self_radius,
+                },
+                ColliderShapes::Sphere { radius },
+            ) => {
+                let self_to_other =
+                    Collider::midpoint_to_other_midpoint(&self, transform, other, other_transform);
+                Some(Collider::collision_sphere_sphere(
+                    self_to_other,
+                    *radius,
+                    *self_radius,
+                ))
             }
-            ColliderShapes::Cuboid {
-                half_width_x,
-                half_height_y,
-                half_depth_z,
-            } => Collider::collision_sphere_cuboid(
+            (
+                ColliderShapes::Sphere { radius },
+                ColliderShapes::Cuboid {
+                    half_width_x,
+                    half_height_y,
+                    half_depth_z,
+                },
+            ) => Some(Collider::collision_sphere_cuboid(
                 &self,
-                self_radius,
+                *radius,
                 other,
                 transform,
                 other_transform,
-                half_width_x,
-                half_height_y,
-                half_depth_z,
-            ),
+                *half_width_x,
+                *half_height_y,
+                *half_depth_z,
+            )),
+            (
+                ColliderShapes::Cuboid {
+                    half_width_x,
+                    half_height_y,
+                    half_depth_z,
+                },
+                ColliderShapes::Sphere { radius },
+            ) => Some(Collider::collision_cuboid_sphere(
+                &self,
+                *half_width_x,
+                *half_height_y,
+                *half_depth_z,
+                other,
+                transform,
+                other_transform,
+                *radius,
+            )),
+            _ => None,
+        }
+    }
+
+    /// the deepest penetration of all pairs of parts
+    fn collision_convex(parts: &[ConvexPart], other_parts: &[ConvexPart]) -> Option<Vec3> {
+        let mut deepest: Option<Vec3> = None;
+        for part in parts.iter() {
+            for other_part in other_parts.iter() {
+                if let Some(penetration) = part.penetration(other_part) {
+                    if deepest.map_or(true, |deepest| {
+                        penetration.length_squared() > deepest.length_squared()
+                    }) {
+                        deepest = Some(penetration);
+                    }
+                }
+            }
         }
+        deepest
     }
 
     fn midpoint_to_other_midpoint(
@@ -165,54 +224,6 @@ impl Collider {
         }
     }
 
-    fn collision_cuboid(
-        &self,
-        self_half_width_x: f32,
-        self_half_height_y: f32,
-        self_half_depth_z: f32,
-        other: &Collider,
-        transform: &Mat4,
-        other_transform: &Mat4,
-    ) -> Option<Vec3> {
-        match other.collider_shape {
-            ColliderShapes::Sphere { radius } => Collider::collision_cuboid_sphere(
-                &self,
-                self_half_width_x,
-                self_half_height_y,
-                self_half_depth_z,
-                other,
-                transform,
-                other_transform,
-                radius,
-            ),
-            ColliderShapes::Cuboid {
-                half_width_x,
-                half_height_y,
-                half_depth_z,
-            } => {
-                let collider: Vec<Vec3> = Collider::compute_vertices(
-                    self_half_width_x,
-                    self_half_height_y,
-                    self_half_depth_z,
-                    self.local_position,
-                    transform,
-                );
-                let other_collider: Vec<Vec3> = Collider::compute_vertices(
-                    half_width_x,
-                    half_height_y,
-                    half_depth_z,
-                    other.local_position,
-                    other_transform,
-                );
-                if let Some(collision) = Collider::gjk(&collider, &other_collider) {
-                    let impulse = Collider::epa(collision.simplex, collider, other_collider);
-                    return Option::Some(impulse.penetration_depth * impulse.normal);
-                }
-                None
-            }
-        }
-    }
-
     fn collision_cuboid_sphere(
         &self,
         self_half_width_x: f32,
@@ -256,9 +267,8 @@ impl Collider {
         }
     }
 
-    fn gjk(vertices: &Vec<Vec3>, other_vertices: &Vec<Vec3>) -> Option<SimplexDirectionCollision> {
-        let mut support: Vec3 =
-            Collider::support(vertices.to_owned(), other_vertices.to_owned(), Vec3::X);
+    fn gjk(part: &ConvexPart, other_part: &ConvexPart) -> Option<SimplexDirectionCollision> {
+        let mut support: Vec3 = Collider::support(part, other_part, Vec3::X);
         let mut simplex = Simplex::new();
         simplex.push_front(support);
 
@@ -268,7 +278,7 @@ impl Collider {
         let mut number_of_iterations: i32 = 0;
         let max_number_of_iterations: i32 = 5;
         while number_of_iterations < max_number_of_iterations {
-            support = Collider::support(vertices.clone(), other_vertices.clone(), direction);
+            support = Collider::support(part, other_part, direction);
 
             if support.dot(direction) <= 0.0 {
                 return None; // no collision
@@ -286,52 +296,14 @@ impl Collider {
         None
     }
 
-    fn find_furthest_point(direction: Vec3, vertices: &Vec<Vec3>) -> Vec3 {
-        *vertices
-            .iter()
-            .max_by(|x, y| x.dot(direction).partial_cmp(&y.dot(direction)).unwrap())
-            .unwrap()
-    }
-
-    fn support(collider_a: Vec<Vec3>, collider_b: Vec<Vec3>, direction: Vec3) -> Vec3 {
-        Collider::find_furthest_point(direction, &collider_a)
-            - Collider::find_furthest_point(-direction, &collider_b)
+    fn support(collider_a: &ConvexPart, collider_b: &ConvexPart, direction: Vec3) -> Vec3 {
+        collider_a.furthest_point(direction) - collider_b.furthest_point(-direction)
     }
 
     fn same_direction(direction: Vec3, point_to_origin: Vec3) -> bool {
         direction.dot(point_to_origin) > 0.0
     }
 
-    fn compute_vertices(
-        half_width_x: f32,
-        half_height_y: f32,
-        half_depth_z: f32,
-        local_position: Vec3,
-        transform: &Mat4,
-    ) -> Vec<Vec3> {
-        let mut edges: Vec<Vec3> = Vec::new();
-        for (x, y, z) in iproduct!(
-            vec![-half_width_x, half_width_x].into_iter(),
-            vec![-half_height_y, half_height_y].into_iter(),
-            vec![-half_depth_z, half_depth_z].into_iter()
-        ) {
-            let local_edge = Collider::compute_local_edge(local_position, x, y, z);
-            let global_edge = Collider::compute_global_edge(transform.clone(), local_edge);
-            edges.push(global_edge);
-        }
-        edges
-    }
-
-    fn compute_local_edge(local_position: Vec3, x: f32, y: f32, z: f32) -> Vec3 {
-        local_position + x * Vec3::X + y * Vec3::Y + z * Vec3::Z
-    }
-
-    fn compute_global_edge(transform: Mat4, local_edge: Vec3) -> Vec3 {
-        let global_edge: Vec4 =
-            transform.mul_vec4(Vec4::new(local_edge.x, local_edge.y, local_edge.z, 1.0));
-        Vec3::new(global_edge.x, global_edge.y, global_edge.z)
-    }
-
     fn next_simplex(points: Simplex, direction: Vec3) -> SimplexDirectionCollision {
         match points.vertices.len() {
             2 => Collider::line(points),
@@ -448,11 +420,12 @@ impl Collider {
         }
     }
 
-    fn epa(simplex: Simplex, collider_a: Vec<Vec3>, collider_b: Vec<Vec3>) -> CollisionPoints {
+    fn epa(simplex: Simplex, collider_a: &ConvexPart, collider_b: &ConvexPart) -> CollisionPoints {
         let mut polytop: Polytop = Polytop::new(simplex.vertices.clone());
 
         let mut min_normal: Vec3 = Vec3::new(0.0, 0.0, 0.0);
         let mut min_distance: f32 = f32::infinity();
+        let mut closest_distance: f32 = 0.0;
 
         let mut normals_min_triangle = Collider::get_face_normals(&polytop.polytop, &polytop.faces);
 
@@ -465,9 +438,9 @@ impl Collider {
             min_normal =
                 normals_min_triangle.normals[normals_min_triangle.min_triangle as usize].xyz();
             min_distance = normals_min_triangle.w(normals_min_triangle.min_triangle as usize);
+            closest_distance = min_distance;
 
-            let support: Vec3 =
-                Collider::support(collider_a.clone(), collider_b.clone(), min_normal);
+            let support: Vec3 = Collider::support(collider_a, collider_b, min_normal);
             let s_distance: f32 = min_normal.dot(support);
 
             if f32::abs(s_distance - min_distance) > 0.01f32 {
@@ -521,7 +494,10 @@ impl Collider {
                 .append(&mut new_normals_min_triangle.normals);
         }
 
-        min_distance = Collider::zero_if_infinity(min_distance);
+        // curved shapes like capsules may not converge, the closest face found so far is a lower bound
+        if min_distance.is_infinite() {
+            min_distance = closest_distance;
+        }
 
         CollisionPoints {
             normal: min_normal,
@@ -529,13 +505,6 @@ impl Collider {
         }
     }
 
-    fn zero_if_infinity(u: f32) -> f32 {
-        if u.is_infinite() {
-            return 0.0f32;
-        }
-        u
-    }
-
     fn get_face_normals(polytope: &Vec<Vec3>, faces: &Vec<usize>) -> FaceNormalsMinTriangle {
         let mut normals: Vec<Vec4> = Vec::new();
         let mut min_triangle: u64 = 0;
@@ -578,6 +547,16 @@ impl Collider {
     }
 }
 
+impl ConvexPart {
+    /// self has to be moved by -penetration to stop overlapping other
+    pub fn penetration(&self, other: &ConvexPart) -> Option<Vec3> {
+        Collider::gjk(self, other).map(|collision| {
+            let impulse = Collider::epa(collision.simplex, self, other);
+            impulse.penetration_depth * impulse.normal
+        })
+    }
+}
+
 struct Polytop {
     polytop: Vec<Vec3>,
     faces: Vec<usize>,
@@ -656,44 +635,18 @@ impl Simplex {
         self
     }
 }
-pub fn collision_update(mut query: Query<(Entity, &Collider, &mut Transform)>) {
-    let mut colliders = Vec::new();
-    let mut other_colliders = Vec::new();
-    for (entity, collider, transform) in query.iter_mut() {
-        colliders.push((entity.clone(), collider.clone(), transform.compute_matrix()));
-        other_colliders.push((entity.clone(), collider.clone(), transform.compute_matrix()));
-    }
-    let mut impulses = HashMap::new();
-    for (entity, collider, collider_transform) in colliders.iter() {
-        for (other_entity, other_collider, other_transform) in other_colliders.iter() {
-            if !entity.id().eq(&other_entity.id()) {
-                let impulse = collider
-                    .detect_collision(other_collider, collider_transform, other_transform)
-                    .unwrap_or(Vec3::ZERO);
-                impulses
-                    .entry(entity.id())
-                    .or_insert(Vec3::ZERO)
-                    .function(impulse);
-            }
-        }
-        other_colliders.remove(0);
-    }
-    &impulses;
-    for (entity, _collider, mut collider_transform) in query.iter_mut() {
-        if impulses.contains_key(&entity.id()) {
-            collider_transform.translation =
-                collider_transform.translation - *impulses.get(&entity.id()).unwrap();
-            collider_transform.translation;
-        }
-    }
-}
-
-trait Vec3Ext {
-    fn function(&mut self, vec: Vec3);
-}
-
-impl Vec3Ext for Vec3 {
-    fn function(&mut self, vec: Vec3) {
-        self.add_assign(vec);
-    }
+/// sensor pairs are overlaps instead of collisions, returns if the pair contains a sensor
+pub(crate) fn record_overlap(
+    contacts: &mut Contacts,
+    (entity, collider): (Entity, &Collider),
+    (other, other_collider): (Entity, &Collider),
+) -> bool {
+    match (collider.sensor, other_collider.sensor) {
+        (true, false) => contacts.add_overlap(entity, other),
+        (false, true) => contacts.add_overlap(other, entity),
+        (false, false) => return false,
+        // sensors do not detect each other
+        (true, true) => {}
+    }
+    true
 }
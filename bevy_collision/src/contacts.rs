@@ -0,0 +1,302 @@
+use std::{collections::HashSet, hash::Hash};
+
+use bevy::prelude::*;
+
+/// what an entity touched, the terrain is not an entity
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ContactPartner {
+    Collider(Entity),
+    Terrain,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Contact {
+    pub entity: Entity,
+    pub other: ContactPartner,
+    /// points from other towards entity
+    pub normal: Vec3,
+    pub depth: f32,
+    /// world space, halfway between the deepest points of both
+    pub point: Vec3,
+}
+
+impl Contact {
+    /// separation moves entity out of other
+    pub fn new(entity: Entity, other: ContactPartner, separation: Vec3, point: Vec3) -> Contact {
+        let depth = separation.length();
+        Contact {
+            entity,
+            other,
+            normal: if depth > 0.0 {
+                separation / depth
+            } else {
+                Vec3::ZERO
+            },
+            depth,
+            point,
+        }
+    }
+
+    /// the same contact seen from the other collider, None for terrain contacts
+    pub fn flipped(&self) -> Option<Contact> {
+        match self.other {
+            ContactPartner::Collider(other) => Some(Contact {
+                entity: other,
+                other: ContactPartner::Collider(self.entity),
+                normal: -self.normal,
+                depth: self.depth,
+                point: self.point,
+            }),
+            ContactPartner::Terrain => None,
+        }
+    }
+
+    /// the pair of the contact independent of which side it was found from
+    fn key(&self) -> (Entity, ContactPartner) {
+        match self.other {
+            ContactPartner::Collider(other) if other.id() < self.entity.id() => {
+                (other, ContactPartner::Collider(self.entity))
+            }
+            _ => (self.entity, self.other),
+        }
+    }
+}
+
+pub struct CollisionStarted {
+    pub entity: Entity,
+    pub other: ContactPartner,
+}
+
+pub struct CollisionEnded {
+    pub entity: Entity,
+    pub other: ContactPartner,
+}
+
+pub trait CollisionPair {
+    fn pair(&self) -> (Entity, ContactPartner);
+
+    /// what touches entity in the collision, None if the collision does not involve it
+    fn partner_of(&self, entity: Entity) -> Option<ContactPartner> {
+        match self.pair() {
+            (first, other) if first == entity => Some(other),
+            (first, ContactPartner::Collider(other)) if other == entity => {
+                Some(ContactPartner::Collider(first))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl CollisionPair for CollisionStarted {
+    fn pair(&self) -> (Entity, ContactPartner) {
+        (self.entity, self.other)
+    }
+}
+
+impl CollisionPair for CollisionEnded {
+    fn pair(&self) -> (Entity, ContactPartner) {
+        (self.entity, self.other)
+    }
+}
+
+/// a collider entered a sensor, sensors do not push colliders out
+pub struct SensorEntered {
+    pub sensor: Entity,
+    pub entity: Entity,
+}
+
+pub struct SensorExited {
+    pub sensor: Entity,
+    pub entity: Entity,
+}
+
+/*
+The collision systems add the contacts they resolve and the sensor overlaps they find during the frame,
+update_contacts publishes them afterwards. Systems reading the contacts therefore see the contacts of the last
+complete frame, independent of the system order.
+ */
+#[derive(Default)]
+pub struct Contacts {
+    contacts: Vec<Contact>,
+    pending: Vec<Contact>,
+    touching: HashSet<(Entity, ContactPartner)>,
+    overlaps: HashSet<(Entity, Entity)>,
+    pending_overlaps: HashSet<(Entity, Entity)>,
+}
+
+/// pairs that changed between two frames
+struct FrameChanges {
+    started: Vec<(Entity, ContactPartner)>,
+    ended: Vec<(Entity, ContactPartner)>,
+    entered: Vec<(Entity, Entity)>,
+    exited: Vec<(Entity, Entity)>,
+}
+
+impl Contacts {
+    pub fn add(&mut self, contact: Contact) {
+        self.pending.push(contact);
+    }
+
+    pub fn add_overlap(&mut self, sensor: Entity, entity: Entity) {
+        self.pending_overlaps.insert((sensor, entity));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Contact> {
+        self.contacts.iter()
+    }
+
+    /// contacts of entity, seen from entity
+    pub fn of(&self, entity: Entity) -> impl Iterator<Item = Contact> + '_ {
+        self.contacts.iter().filter_map(move |contact| {
+            if contact.entity == entity {
+                Some(*contact)
+            } else if contact.other == ContactPartner::Collider(entity) {
+                contact.flipped()
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn touching(&self, entity: Entity, other: ContactPartner) -> bool {
+        self.of(entity).any(|contact| contact.other == other)
+    }
+
+    /// colliders inside of sensor
+    pub fn overlapping(&self, sensor: Entity) -> impl Iterator<Item = Entity> + '_ {
+        self.overlaps
+            .iter()
+            .filter(move |(overlapped_sensor, _)| *overlapped_sensor == sensor)
+            .map(|(_, entity)| *entity)
+    }
+
+    /// publishes the pending contacts and overlaps
+    fn finish_frame(&mut self) -> FrameChanges {
+        self.contacts = std::mem::take(&mut self.pending);
+        let touching: HashSet<(Entity, ContactPartner)> =
+            self.contacts.iter().map(Contact::key).collect();
+        let (started, ended) = changes(&mut self.touching, touching);
+        let overlaps = std::mem::take(&mut self.pending_overlaps);
+        let (entered, exited) = changes(&mut self.overlaps, overlaps);
+        FrameChanges {
+            started,
+            ended,
+            entered,
+            exited,
+        }
+    }
+}
+
+/// replaces previous by current, returns the added and the removed pairs ordered by their first entity
+fn changes<T: Copy + Eq + Hash>(
+    previous: &mut HashSet<(Entity, T)>,
+    current: HashSet<(Entity, T)>,
+) -> (Vec<(Entity, T)>, Vec<(Entity, T)>) {
+    let mut added: Vec<(Entity, T)> = current.difference(previous).copied().collect();
+    let mut removed: Vec<(Entity, T)> = previous.difference(&current).copied().collect();
+    *previous = current;
+    added.sort_by_key(|(entity, _)| entity.id());
+    removed.sort_by_key(|(entity, _)| entity.id());
+    (added, removed)
+}
+
+pub fn update_contacts(
+    mut contacts: ResMut<Contacts>,
+    mut started_events: ResMut<Events<CollisionStarted>>,
+    mut ended_events: ResMut<Events<CollisionEnded>>,
+    mut entered_events: ResMut<Events<SensorEntered>>,
+    mut exited_events: ResMut<Events<SensorExited>>,
+) {
+    let changes = contacts.finish_frame();
+    for (entity, other) in changes.started {
+        started_events.send(CollisionStarted { entity, other });
+    }
+    for (entity, other) in changes.ended {
+        ended_events.send(CollisionEnded { entity, other });
+    }
+    for (sensor, entity) in changes.entered {
+        entered_events.send(SensorEntered { sensor, entity });
+    }
+    for (sensor, entity) in changes.exited {
+        exited_events.send(SensorExited { sensor, entity });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{Entity, Vec3};
+
+    use super::{Contact, ContactPartner, Contacts};
+
+    fn contact(entity: u32, other: ContactPartner) -> Contact {
+        Contact::new(Entity::new(entity), other, Vec3::Y * 0.2, Vec3::ZERO)
+    }
+
+    #[test]
+    fn collisions_start_and_end_once() {
+        let mut contacts = Contacts::default();
+        let terrain = ContactPartner::Terrain;
+        let second = ContactPartner::Collider(Entity::new(2));
+
+        contacts.add(contact(1, terrain));
+        contacts.add(contact(2, ContactPartner::Collider(Entity::new(1))));
+        let changes = contacts.finish_frame();
+        let (started, ended) = (changes.started, changes.ended);
+        assert_eq!(started.len(), 2);
+        assert!(started.contains(&(Entity::new(1), terrain)));
+        assert!(started.contains(&(Entity::new(1), second)));
+        assert!(ended.is_empty());
+
+        // the same pair found from the other side is still the same collision
+        contacts.add(contact(1, second));
+        let changes = contacts.finish_frame();
+        let (started, ended) = (changes.started, changes.ended);
+        assert!(started.is_empty());
+        assert_eq!(ended, vec![(Entity::new(1), terrain)]);
+
+        let changes = contacts.finish_frame();
+        assert!(changes.started.is_empty());
+        assert_eq!(changes.ended, vec![(Entity::new(1), second)]);
+    }
+
+    #[test]
+    fn contacts_are_seen_from_both_sides() {
+        let mut contacts = Contacts::default();
+        contacts.add(contact(1, ContactPartner::Collider(Entity::new(2))));
+        contacts.finish_frame();
+
+        let seen_from_other: Vec<Contact> = contacts.of(Entity::new(2)).collect();
+        assert_eq!(seen_from_other.len(), 1);
+        assert_eq!(
+            seen_from_other[0].other,
+            ContactPartner::Collider(Entity::new(1))
+        );
+        assert_eq!(seen_from_other[0].normal, -Vec3::Y);
+        assert!((seen_from_other[0].depth - 0.2).abs() < 0.0001);
+        assert_eq!(seen_from_other[0].point, Vec3::ZERO);
+        assert!(contacts.touching(Entity::new(1), ContactPartner::Collider(Entity::new(2))));
+        assert!(!contacts.touching(Entity::new(1), ContactPartner::Terrain));
+    }
+
+    #[test]
+    fn sensors_report_entering_and_exiting_colliders() {
+        let mut contacts = Contacts::default();
+        let sensor = Entity::new(1);
+
+        contacts.add_overlap(sensor, Entity::new(2));
+        let changes = contacts.finish_frame();
+        assert_eq!(changes.entered, vec![(sensor, Entity::new(2))]);
+        assert!(changes.started.is_empty());
+
+        contacts.add_overlap(sensor, Entity::new(2));
+        contacts.add_overlap(sensor, Entity::new(3));
+        let changes = contacts.finish_frame();
+        assert_eq!(changes.entered, vec![(sensor, Entity::new(3))]);
+        assert!(changes.exited.is_empty());
+        assert_eq!(contacts.overlapping(sensor).count(), 2);
+
+        let changes = contacts.finish_frame();
+        assert_eq!(changes.exited.len(), 2);
+        assert_eq!(contacts.overlapping(sensor).count(), 0);
+    }
+}
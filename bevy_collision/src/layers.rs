@@ -0,0 +1,85 @@
+use std::ops::BitOr;
+
+/// bit set of collision layers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CollisionLayers(u32);
+
+impl CollisionLayers {
+    pub const NONE: CollisionLayers = CollisionLayers(0);
+    pub const ALL: CollisionLayers = CollisionLayers(u32::MAX);
+    /// layer of colliders that did not choose one
+    pub const DEFAULT: CollisionLayers = CollisionLayers(1);
+    pub const PLAYER: CollisionLayers = CollisionLayers(1 << 1);
+    pub const NPC: CollisionLayers = CollisionLayers(1 << 2);
+    pub const PICKUP: CollisionLayers = CollisionLayers(1 << 3);
+    pub const DEBRIS: CollisionLayers = CollisionLayers(1 << 4);
+    /// the voxel terrain, it is not a collider but can be masked like one
+    pub const TERRAIN: CollisionLayers = CollisionLayers(1 << 5);
+
+    pub fn intersects(self, other: CollisionLayers) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn without(self, other: CollisionLayers) -> CollisionLayers {
+        CollisionLayers(self.0 & !other.0)
+    }
+}
+
+impl BitOr for CollisionLayers {
+    type Output = CollisionLayers;
+
+    fn bitor(self, other: CollisionLayers) -> CollisionLayers {
+        CollisionLayers(self.0 | other.0)
+    }
+}
+
+/// layers the collider is part of and the layers it collides with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionFilter {
+    pub layers: CollisionLayers,
+    pub mask: CollisionLayers,
+}
+
+impl CollisionFilter {
+    pub fn new(layers: CollisionLayers, mask: CollisionLayers) -> CollisionFilter {
+        CollisionFilter { layers, mask }
+    }
+
+    /// both have to accept each other, so one side alone can ignore the other
+    pub fn collides_with(&self, other: &CollisionFilter) -> bool {
+        self.mask.intersects(other.layers) && other.mask.intersects(self.layers)
+    }
+
+    pub fn collides_with_terrain(&self) -> bool {
+        self.mask.intersects(CollisionLayers::TERRAIN)
+    }
+}
+
+impl Default for CollisionFilter {
+    fn default() -> Self {
+        CollisionFilter::new(CollisionLayers::DEFAULT, CollisionLayers::ALL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CollisionFilter, CollisionLayers};
+
+    #[test]
+    fn colliders_collide_only_if_both_accept_each_other() {
+        let npc = CollisionFilter::new(
+            CollisionLayers::NPC,
+            CollisionLayers::ALL.without(CollisionLayers::NPC),
+        );
+        let player = CollisionFilter::new(CollisionLayers::PLAYER, CollisionLayers::ALL);
+        let ghost = CollisionFilter::new(CollisionLayers::DEFAULT, CollisionLayers::NONE);
+
+        assert!(!npc.collides_with(&npc));
+        assert!(npc.collides_with(&player));
+        assert!(player.collides_with(&npc));
+        assert!(!player.collides_with(&ghost));
+        assert!(CollisionFilter::default().collides_with(&CollisionFilter::default()));
+        assert!(npc.collides_with_terrain());
+        assert!(!ghost.collides_with_terrain());
+    }
+}
@@ -1 +1,6 @@
+pub mod broad_phase;
 pub mod collider;
+pub mod contacts;
+pub mod layers;
+pub mod rigid_body;
+pub mod shapes;
@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    broad_phase::colliding_pairs,
+    collider::{record_overlap, Collider, ColliderShapes},
+    contacts::{Contact, ContactPartner, Contacts},
+};
+
+/// speeds below this are treated as resting contacts to avoid jittering
+const RESTING_SPEED: f32 = 0.01;
+
+/// dynamic body moved by the integrator, colliders without a rigid body are static for rigid bodies
+#[derive(Clone, Copy, Debug)]
+pub struct RigidBody {
+    /// kg, not positive or infinite masses are immovable
+    pub mass: f32,
+    /// m/s
+    pub velocity: Vec3,
+    /// rad/s around the axis of the vector
+    pub angular_velocity: Vec3,
+    /// 0 stops the body at contacts, 1 bounces back without losing energy
+    pub restitution: f32,
+    pub friction: f32,
+    pub gravity_scale: f32,
+    /// m/s², replaces PhysicsSettings::gravity, e.g. while the body is lashed to a surface
+    pub gravity: Option<Vec3>,
+}
+
+impl RigidBody {
+    pub fn new(mass: f32) -> RigidBody {
+        RigidBody {
+            mass,
+            velocity: Vec3::ZERO,
+            angular_velocity: Vec3::ZERO,
+            restitution: 0.2,
+            friction: 0.5,
+            gravity_scale: 1.0,
+            gravity: None,
+        }
+    }
+
+    pub fn inverse_mass(&self) -> f32 {
+        if self.mass > 0.0 && self.mass.is_finite() {
+            1.0 / self.mass
+        } else {
+            0.0
+        }
+    }
+
+    /// offset from the center of mass to the point the impulse is applied at,
+    /// the inertia is approximated by a solid sphere of radius
+    pub fn apply_impulse(&mut self, impulse: Vec3, offset: Vec3, radius: f32) {
+        let inverse_mass = self.inverse_mass();
+        self.velocity += impulse * inverse_mass;
+        if radius > 0.0 {
+            let inverse_inertia = inverse_mass / (0.4 * radius * radius);
+            self.angular_velocity += offset.cross(impulse) * inverse_inertia;
+        }
+    }
+}
+
+pub struct PhysicsSettings {
+    /// m/s²
+    pub gravity: Vec3,
+    /// m/s, faster bodies could pass through thin colliders within a frame
+    pub max_speed: f32,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        PhysicsSettings {
+            gravity: Vec3::new(0.0, -9.81, 0.0),
+            max_speed: 50.0,
+        }
+    }
+}
+
+/// Impulse on body a for a contact with body b, None is a static collider.
+/// normal points from b towards a. Contains the bounce along the normal and the Coulomb friction along the contact.
+pub fn contact_impulse(a: &RigidBody, b: Option<&RigidBody>, normal: Vec3) -> Vec3 {
+    let (b_velocity, b_inverse_mass, restitution, friction) = match b {
+        Some(b) => (
+            b.velocity,
+            b.inverse_mass(),
+            a.restitution.min(b.restitution),
+            (a.friction * b.friction).sqrt(),
+        ),
+        None => (Vec3::ZERO, 0.0, a.restitution, a.friction),
+    };
+    let inverse_mass = a.inverse_mass() + b_inverse_mass;
+    let relative_velocity = a.velocity - b_velocity;
+    let normal_speed = relative_velocity.dot(normal);
+    if inverse_mass == 0.0 || normal_speed >= 0.0 {
+        return Vec3::ZERO;
+    }
+    let restitution = if -normal_speed < RESTING_SPEED {
+        0.0
+    } else {
+        restitution
+    };
+    let normal_impulse = -(1.0 + restitution) * normal_speed / inverse_mass;
+
+    let tangent_velocity = relative_velocity - normal * normal_speed;
+    let tangent_speed = tangent_velocity.length();
+    let friction_impulse = if tangent_speed > 0.0 {
+        let stopping = tangent_speed / inverse_mass;
+        -tangent_velocity / tangent_speed * stopping.min(friction * normal_impulse)
+    } else {
+        Vec3::ZERO
+    };
+    normal * normal_impulse + friction_impulse
+}
+
+pub fn integrate_rigid_bodies(
+    time: Res<Time>,
+    settings: Res<PhysicsSettings>,
+    mut body_query: Query<(&mut RigidBody, &mut Transform)>,
+) {
+    let delta = time.delta_seconds();
+    for (mut body, mut transform) in body_query.iter_mut() {
+        if body.inverse_mass() == 0.0 {
+            continue;
+        }
+        let gravity = body.gravity.unwrap_or(settings.gravity);
+        body.velocity += gravity * body.gravity_scale * delta;
+        if body.velocity.length() > settings.max_speed {
+            body.velocity = body.velocity.normalize() * settings.max_speed;
+        }
+        transform.translation += body.velocity * delta;
+        let angle = body.angular_velocity.length() * delta;
+        if angle > 0.0 {
+            transform.rotation = Quat::from_axis_angle(body.angular_velocity.normalize(), angle)
+                * transform.rotation;
+        }
+    }
+}
+
+/*
+Resolves the contacts of all colliders, the pairs found by the broad phase are computed once per frame.
+For rigid bodies the penetration is removed by moving the bodies apart in relation to their masses,
+afterwards the velocities are changed by the contact impulses.
+Contacts are resolved sequentially, so the impulses of a contact see the velocities after the previous contacts.
+Of two colliders without rigid bodies the first one is pushed out of the other.
+ */
+pub fn rigid_body_collision_update(
+    mut contacts: ResMut<Contacts>,
+    mut query: Query<(Entity, &Collider, &mut Transform, Option<&mut RigidBody>)>,
+) {
+    let mut moves: HashMap<Entity, Vec3> = HashMap::new();
+    let mut bodies: HashMap<Entity, RigidBody> = HashMap::new();
+    {
+        let colliders: Vec<(Entity, &Collider, Mat4, Option<RigidBody>)> = query
+            .iter_mut()
+            .map(|(entity, collider, transform, body)| {
+                (
+                    entity,
+                    collider,
+                    transform.compute_matrix(),
+                    body.map(|b| *b),
+                )
+            })
+            .collect();
+        for (entity, _, _, body) in colliders.iter() {
+            if let Some(body) = body {
+                bodies.insert(*entity, *body);
+            }
+        }
+
+        let shapes: Vec<(&Collider, Mat4)> = colliders
+            .iter()
+            .map(|(_, collider, transform, _)| (*collider, *transform))
+            .collect();
+        for collision in colliding_pairs(&shapes) {
+            let (entity, collider, _, body) = &colliders[collision.first];
+            let (other_entity, other_collider, _, other_body) = &colliders[collision.second];
+            if record_overlap(
+                &mut contacts,
+                (*entity, *collider),
+                (*other_entity, *other_collider),
+            ) {
+                continue;
+            }
+            let penetration = collision.penetration;
+            contacts.add(Contact::new(
+                *entity,
+                ContactPartner::Collider(*other_entity),
+                -penetration,
+                collision.point,
+            ));
+            if body.is_none() && other_body.is_none() {
+                *moves.entry(*entity).or_insert(Vec3::ZERO) -= penetration;
+                continue;
+            }
+            let inverse_mass = body.map(|b| b.inverse_mass()).unwrap_or(0.0);
+            let other_inverse_mass = other_body.map(|b| b.inverse_mass()).unwrap_or(0.0);
+            let total_inverse_mass = inverse_mass + other_inverse_mass;
+            if total_inverse_mass == 0.0 {
+                continue;
+            }
+            *moves.entry(*entity).or_insert(Vec3::ZERO) -=
+                penetration * inverse_mass / total_inverse_mass;
+            *moves.entry(*other_entity).or_insert(Vec3::ZERO) +=
+                penetration * other_inverse_mass / total_inverse_mass;
+
+            let normal = -penetration.normalize();
+            resolve_pair(
+                &mut bodies,
+                (*entity, collider.collider_shape.bounding_radius()),
+                (
+                    *other_entity,
+                    other_collider.collider_shape.bounding_radius(),
+                ),
+                normal,
+            );
+        }
+    }
+
+    for (entity, _, mut transform, body) in query.iter_mut() {
+        if let Some(offset) = moves.get(&entity) {
+            transform.translation += *offset;
+        }
+        if let (Some(mut body), Some(resolved)) = (body, bodies.get(&entity)) {
+            *body = *resolved;
+        }
+    }
+}
+
+/// normal points from b towards a, the impulses are applied at the surface of the bounding spheres
+fn resolve_pair(
+    bodies: &mut HashMap<Entity, RigidBody>,
+    (a, a_radius): (Entity, f32),
+    (b, b_radius): (Entity, f32),
+    normal: Vec3,
+) {
+    let b_body = bodies.get(&b).copied();
+    let impulse = match bodies.get(&a) {
+        Some(a_body) => contact_impulse(a_body, b_body.as_ref(), normal),
+        // a is static, the contact is seen from b
+        None => match b_body {
+            Some(b_body) => -contact_impulse(&b_body, None, -normal),
+            None => return,
+        },
+    };
+    if let Some(a_body) = bodies.get_mut(&a) {
+        a_body.apply_impulse(impulse, -normal * a_radius, a_radius);
+    }
+    if let Some(b_body) = bodies.get_mut(&b) {
+        b_body.apply_impulse(-impulse, normal * b_radius, b_radius);
+    }
+}
+
+impl ColliderShapes {
+    /// radius of a sphere around the local position containing the shape
+    pub fn bounding_radius(&self) -> f32 {
+        match self {
+            ColliderShapes::Sphere { radius } => *radius,
+            ColliderShapes::Cuboid {
+                half_width_x,
+                half_height_y,
+                half_depth_z,
+            } => Vec3::new(*half_width_x, *half_height_y, *half_depth_z).length(),
+            ColliderShapes::Capsule {
+                half_height,
+                radius,
+            } => half_height + radius,
+            ColliderShapes::ConvexHull { vertices } => vertices
+                .iter()
+                .map(|vertex| vertex.length())
+                .fold(0.0, f32::max),
+            ColliderShapes::Compound { children } => children
+                .iter()
+                .map(|(local_transform, child)| {
+                    local_transform.transform_point3(Vec3::ZERO).length() + child.bounding_radius()
+                })
+                .fold(0.0, f32::max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Vec3;
+
+    use super::{contact_impulse, RigidBody};
+
+    fn body(velocity: Vec3, restitution: f32, friction: f32) -> RigidBody {
+        RigidBody {
+            velocity,
+            restitution,
+            friction,
+            ..RigidBody::new(2.0)
+        }
+    }
+
+    #[test]
+    fn bodies_bounce_off_static_colliders() {
+        let mut falling = body(Vec3::new(0.0, -4.0, 0.0), 0.5, 0.0);
+        let impulse = contact_impulse(&falling, None, Vec3::Y);
+        falling.apply_impulse(impulse, Vec3::ZERO, 0.0);
+
+        assert!((falling.velocity - Vec3::new(0.0, 2.0, 0.0)).length() < 1e-5);
+        assert_eq!(contact_impulse(&falling, None, Vec3::Y), Vec3::ZERO);
+    }
+
+    #[test]
+    fn inelastic_contacts_conserve_momentum() {
+        let mut a = body(Vec3::new(-1.0, 0.0, 0.0), 0.0, 0.0);
+        let mut b = body(Vec3::new(3.0, 0.0, 0.0), 0.0, 0.0);
+        b.mass = 6.0;
+        let momentum = a.velocity * a.mass + b.velocity * b.mass;
+
+        let impulse = contact_impulse(&a, Some(&b), Vec3::X);
+        a.apply_impulse(impulse, Vec3::ZERO, 0.0);
+        b.apply_impulse(-impulse, Vec3::ZERO, 0.0);
+
+        assert!((a.velocity - b.velocity).length() < 1e-5);
+        assert!((a.velocity * a.mass + b.velocity * b.mass - momentum).length() < 1e-4);
+    }
+
+    #[test]
+    fn friction_is_limited_by_the_normal_impulse() {
+        let sliding = body(Vec3::new(10.0, -1.0, 0.0), 0.0, 0.5);
+        let impulse = contact_impulse(&sliding, None, Vec3::Y);
+
+        assert!((impulse.y - 2.0).abs() < 1e-5);
+        assert!((impulse.x + 1.0).abs() < 1e-5);
+
+        let slow = body(Vec3::new(0.1, -1.0, 0.0), 0.0, 0.5);
+        let impulse = contact_impulse(&slow, None, Vec3::Y);
+        assert!((slow.velocity.x + impulse.x / slow.mass).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,271 @@
+use bevy::{
+    prelude::*,
+    render::mesh::{Mesh, VertexAttributeValues},
+};
+use itertools::iproduct;
+
+use crate::{broad_phase::Aabb, collider::ColliderShapes};
+
+/*
+A convex piece of a collider in world space. GJK and EPA only need the point furthest in a direction,
+so the points of a hull do not have to be its corners only, every point inside the hull is ignored by the support.
+ */
+#[derive(Clone, Debug)]
+pub enum ConvexPart {
+    Points(Vec<Vec3>),
+    Sphere { center: Vec3, radius: f32 },
+    Capsule { start: Vec3, end: Vec3, radius: f32 },
+}
+
+impl ConvexPart {
+    /// axis aligned box, e.g. a terrain voxel
+    pub fn cuboid(center: Vec3, half_extents: Vec3) -> ConvexPart {
+        ConvexPart::Points(
+            box_corners(half_extents)
+                .into_iter()
+                .map(|corner| center + corner)
+                .collect(),
+        )
+    }
+
+    pub fn furthest_point(&self, direction: Vec3) -> Vec3 {
+        match self {
+            // the origin for a part without points, convex_parts never builds one
+            ConvexPart::Points(points) => points
+                .iter()
+                .copied()
+                .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+                .unwrap_or(Vec3::ZERO),
+            ConvexPart::Sphere { center, radius } => *center + rounding(direction, *radius),
+            ConvexPart::Capsule { start, end, radius } => {
+                let furthest_end = if start.dot(direction) > end.dot(direction) {
+                    *start
+                } else {
+                    *end
+                };
+                furthest_end + rounding(direction, *radius)
+            }
+        }
+    }
+
+    /// distance reached in direction and the center of the points reaching it, e.g. the center of a face
+    pub(crate) fn support_point(&self, direction: Vec3) -> (f32, Vec3) {
+        let tolerance = 0.001 * direction.length();
+        let (furthest, support) = match self {
+            ConvexPart::Points(points) => {
+                let furthest = points
+                    .iter()
+                    .map(|point| point.dot(direction))
+                    .fold(f32::NEG_INFINITY, f32::max);
+                let support: Vec<Vec3> = points
+                    .iter()
+                    .filter(|point| point.dot(direction) >= furthest - tolerance)
+                    .copied()
+                    .collect();
+                (furthest, support)
+            }
+            ConvexPart::Sphere { .. } => {
+                let point = self.furthest_point(direction);
+                (point.dot(direction), vec![point])
+            }
+            ConvexPart::Capsule { start, end, .. } => {
+                let point = self.furthest_point(direction);
+                let offset = point
+                    - if start.dot(direction) > end.dot(direction) {
+                        *start
+                    } else {
+                        *end
+                    };
+                // a capsule lying on its side touches along its whole segment
+                if (start.dot(direction) - end.dot(direction)).abs() <= tolerance {
+                    (point.dot(direction), vec![*start + offset, *end + offset])
+                } else {
+                    (point.dot(direction), vec![point])
+                }
+            }
+        };
+        let center =
+            support.iter().fold(Vec3::ZERO, |sum, point| sum + *point) / support.len() as f32;
+        (furthest, center)
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        match self {
+            ConvexPart::Points(points) => aabb_of(points),
+            ConvexPart::Sphere { center, radius } => Aabb {
+                min: *center - Vec3::splat(*radius),
+                max: *center + Vec3::splat(*radius),
+            },
+            ConvexPart::Capsule { start, end, radius } => Aabb {
+                min: start.min(*end) - Vec3::splat(*radius),
+                max: start.max(*end) + Vec3::splat(*radius),
+            },
+        }
+    }
+}
+
+fn rounding(direction: Vec3, radius: f32) -> Vec3 {
+    if direction.length_squared() > 0.0 {
+        direction.normalize() * radius
+    } else {
+        Vec3::ZERO
+    }
+}
+
+fn box_corners(half_extents: Vec3) -> Vec<Vec3> {
+    iproduct!(
+        vec![-half_extents.x, half_extents.x].into_iter(),
+        vec![-half_extents.y, half_extents.y].into_iter(),
+        vec![-half_extents.z, half_extents.z].into_iter()
+    )
+    .map(|(x, y, z)| Vec3::new(x, y, z))
+    .collect()
+}
+
+pub(crate) fn aabb_of(points: &[Vec3]) -> Aabb {
+    points.iter().fold(
+        Aabb {
+            min: Vec3::splat(f32::INFINITY),
+            max: Vec3::splat(f32::NEG_INFINITY),
+        },
+        |aabb, point| Aabb {
+            min: aabb.min.min(*point),
+            max: aabb.max.max(*point),
+        },
+    )
+}
+
+impl ColliderShapes {
+    /// hull around all vertex positions of the mesh, None if the mesh has no positions
+    pub fn convex_hull(mesh: &Mesh) -> Option<ColliderShapes> {
+        match mesh.attribute(Mesh::ATTRIBUTE_POSITION)? {
+            VertexAttributeValues::Float32x3(positions) if !positions.is_empty() => {
+                Some(ColliderShapes::ConvexHull {
+                    vertices: positions.iter().map(|p| Vec3::from(*p)).collect(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// the shape in world space, transform includes the local position of the collider
+    pub(crate) fn convex_parts(&self, transform: &Mat4, parts: &mut Vec<ConvexPart>) {
+        match self {
+            ColliderShapes::Sphere { radius } => parts.push(ConvexPart::Sphere {
+                center: transform.transform_point3(Vec3::ZERO),
+                radius: *radius,
+            }),
+            ColliderShapes::Cuboid {
+                half_width_x,
+                half_height_y,
+                half_depth_z,
+            } => parts.push(ConvexPart::Points(
+                box_corners(Vec3::new(*half_width_x, *half_height_y, *half_depth_z))
+                    .into_iter()
+                    .map(|corner| transform.transform_point3(corner))
+                    .collect(),
+            )),
+            ColliderShapes::Capsule {
+                half_height,
+                radius,
+            } => parts.push(ConvexPart::Capsule {
+                start: transform.transform_point3(-Vec3::Y * *half_height),
+                end: transform.transform_point3(Vec3::Y * *half_height),
+                radius: *radius,
+            }),
+            // a hull without vertices has no volume and touches nothing
+            ColliderShapes::ConvexHull { vertices } if vertices.is_empty() => {}
+            ColliderShapes::ConvexHull { vertices } => parts.push(ConvexPart::Points(
+                vertices
+                    .iter()
+                    .map(|vertex| transform.transform_point3(*vertex))
+                    .collect(),
+            )),
+            ColliderShapes::Compound { children } => {
+                for (local_transform, child) in children.iter() {
+                    child.convex_parts(&(*transform * *local_transform), parts);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{shape, Mat4, Mesh, Vec3};
+
+    use crate::collider::ColliderShapes;
+
+    use super::ConvexPart;
+
+    #[test]
+    fn capsules_reach_radius_beyond_their_segment() {
+        let capsule = ConvexPart::Capsule {
+            start: Vec3::new(0.0, -1.0, 0.0),
+            end: Vec3::new(0.0, 1.0, 0.0),
+            radius: 0.5,
+        };
+
+        assert_eq!(capsule.furthest_point(Vec3::Y), Vec3::new(0.0, 1.5, 0.0));
+        assert_eq!(capsule.furthest_point(-Vec3::X), Vec3::new(-0.5, 1.0, 0.0));
+        let (distance, center) = capsule.support_point(Vec3::X);
+        assert_eq!(distance, 0.5);
+        assert_eq!(center, Vec3::new(0.5, 0.0, 0.0));
+        assert_eq!(capsule.aabb().min, Vec3::new(-0.5, -1.5, -0.5));
+    }
+
+    #[test]
+    fn convex_hulls_are_built_from_mesh_positions() {
+        let hull = ColliderShapes::convex_hull(&Mesh::from(shape::Cube { size: 2.0 })).unwrap();
+        let mut parts = Vec::new();
+        hull.convex_parts(&Mat4::from_translation(Vec3::X), &mut parts);
+
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].furthest_point(Vec3::ONE), Vec3::new(2.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn empty_hulls_have_no_parts() {
+        let hull = ColliderShapes::ConvexHull {
+            vertices: Vec::new(),
+        };
+        let mut parts = Vec::new();
+        hull.convex_parts(&Mat4::IDENTITY, &mut parts);
+
+        assert!(parts.is_empty());
+        assert_eq!(
+            ConvexPart::Points(Vec::new()).furthest_point(Vec3::X),
+            Vec3::ZERO
+        );
+    }
+
+    #[test]
+    fn furthest_point_does_not_panic_on_nan() {
+        let points = ConvexPart::cuboid(Vec3::ZERO, Vec3::ONE);
+
+        assert!(points
+            .furthest_point(Vec3::new(f32::NAN, 0.0, 0.0))
+            .is_finite());
+        let nan_point = ConvexPart::Points(vec![Vec3::ONE, Vec3::splat(f32::NAN)]);
+        nan_point.furthest_point(Vec3::X);
+    }
+
+    #[test]
+    fn compound_children_are_placed_by_their_local_transforms() {
+        let compound = ColliderShapes::Compound {
+            children: vec![
+                (
+                    Mat4::from_translation(Vec3::new(0.0, 1.0, 0.0)),
+                    ColliderShapes::Sphere { radius: 0.5 },
+                ),
+                (Mat4::IDENTITY, ColliderShapes::cube(1.0)),
+            ],
+        };
+        let mut parts = Vec::new();
+        compound.convex_parts(&Mat4::from_translation(Vec3::Z), &mut parts);
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].furthest_point(Vec3::Y), Vec3::new(0.0, 1.5, 1.0));
+        assert_eq!(parts[1].aabb().max, Vec3::new(0.5, 0.5, 1.5));
+    }
+}
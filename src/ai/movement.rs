@@ -1,5 +1,3 @@
-use std::sync::Arc;
-
 use crate::delayed_despawn::DelayedDespawns;
 use crate::particles::model::ParticleDescription;
 use crate::particles::DelayedParticleSpawns;
@@ -84,14 +82,10 @@ pub fn update_behaviour_system(
                             .push((Timer::from_seconds(2.1, false), entity));
                         let center = npc_transform.translation.clone();
 
-                        let delete =
-                            Arc::new(move |_: &VoxelAccess| VoxelPosition::sphere(&center, 10.0));
+                        let delete = move |_: &VoxelAccess| VoxelPosition::sphere(&center, 10.0);
                         world_transformations.transformations.push((
                             Timer::from_seconds(2.1, false),
-                            WorldUpdateEvent {
-                                delete: delete,
-                                replace: false,
-                            },
+                            WorldUpdateEvent::deletion(delete, false),
                         ));
 
                         effects_res.effects.push((
@@ -2,6 +2,10 @@ use std::time::Duration;
 
 use crate::ai::model::*;
 use bevy::prelude::*;
+use bevy_collision::{
+    collider::{Collider, ColliderShapes},
+    layers::{CollisionFilter, CollisionLayers},
+};
 use common::{Movable, UnitRotation};
 use rand::prelude::*;
 
@@ -25,9 +29,8 @@ pub fn enemy_spawn_system(
                 .timer
                 .set_duration(Duration::from_millis(rng.gen_range(500..2000)));
 
-            let cube_handle = meshes.add(Mesh::from(shape::Cube {
-                size: rng.gen_range(0.5f32..5.0f32),
-            }));
+            let size = rng.gen_range(0.5f32..5.0f32);
+            let cube_handle = meshes.add(Mesh::from(shape::Cube { size }));
             let cube_material_handle = materials.add(StandardMaterial {
                 base_color: Color::rgb(1.0, 0.0, rng.gen_range(0.0f32..1.0f32)),
                 ..Default::default()
@@ -49,6 +52,16 @@ pub fn enemy_spawn_system(
                     behaviour: NPCBehaviours::RANDOM,
                     velocity: rng.gen_range(1.0f32..5.0f32),
                 })
+                // npcs pass through each other, but not through the player or the terrain
+                .insert(Collider {
+                    collider_shape: ColliderShapes::cube(size),
+                    local_position: Vec3::ZERO,
+                    filter: CollisionFilter::new(
+                        CollisionLayers::NPC,
+                        CollisionLayers::ALL.without(CollisionLayers::NPC),
+                    ),
+                    sensor: false,
+                })
                 .insert(Movable)
                 .insert(UnitRotation {
                     ..Default::default()
@@ -5,6 +5,7 @@ mod movement;
 mod particles;
 mod pickups;
 mod player;
+mod skills;
 mod unit_effects;
 
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
@@ -19,13 +20,27 @@ use crate::delayed_despawn::DelayedDespawnsPlugin;
 use crate::movement::MovementPlugin;
 use crate::particles::ParticlePlugin;
 use crate::player::PlayerPlugin;
-use bevy_collision::collider::collision_update;
+use crate::skills::SkillPlugin;
+use bevy_collision::{
+    contacts::{
+        update_contacts, CollisionEnded, CollisionStarted, Contacts, SensorEntered, SensorExited,
+    },
+    rigid_body::{integrate_rigid_bodies, rigid_body_collision_update, PhysicsSettings},
+};
 use voxel::{access::VoxelAccess, collision::systems::terrain_collision_system, WorldPlugin};
 
 use mimalloc::MiMalloc;
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
+/// bodies are moved first, then pushed out of each other and finally out of the terrain
+#[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
+enum PhysicsSystem {
+    Integration,
+    Collisions,
+    Terrain,
+}
+
 fn main() {
     App::build()
         .insert_resource(DefaultTaskPoolOptions::with_num_threads(8))
@@ -36,6 +51,7 @@ fn main() {
         .add_plugin(AIPlugin)
         .add_plugin(MovementPlugin)
         .add_plugin(PlayerPlugin)
+        .add_plugin(SkillPlugin)
         .add_plugin(CloudPlugin)
         .add_plugin(DelayedDespawnsPlugin)
         .add_plugin(FrameTimeDiagnosticsPlugin::default())
@@ -46,8 +62,32 @@ fn main() {
         .add_startup_system(window_setup.system())
         .add_system(bevy::input::system::exit_on_esc_system.system())
         .insert_resource(VoxelAccess::new())
-        .add_system(terrain_collision_system.system())
-        .add_system(collision_update.system())
+        .init_resource::<PhysicsSettings>()
+        .add_system(
+            integrate_rigid_bodies
+                .system()
+                .label(PhysicsSystem::Integration)
+                .before(PhysicsSystem::Collisions),
+        )
+        .add_system(
+            rigid_body_collision_update
+                .system()
+                .label(PhysicsSystem::Collisions)
+                .after(PhysicsSystem::Integration)
+                .before(PhysicsSystem::Terrain),
+        )
+        .add_system(
+            terrain_collision_system
+                .system()
+                .label(PhysicsSystem::Terrain)
+                .after(PhysicsSystem::Collisions),
+        )
+        .init_resource::<Contacts>()
+        .add_event::<CollisionStarted>()
+        .add_event::<CollisionEnded>()
+        .add_event::<SensorEntered>()
+        .add_event::<SensorExited>()
+        .add_system_to_stage(CoreStage::PostUpdate, update_contacts.system())
         .run();
 }
 
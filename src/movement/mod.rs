@@ -36,7 +36,7 @@ fn movement_system(
                 player_position.position = transform.translation;
             }
         } else {
-            dbg!("Could not find entity");
+            warn!("Could not find the moved entity {:?}", movement.entity);
         }
     }
 }
@@ -1,11 +1,19 @@
 use std::time::Duration;
 
 use bevy::prelude::*;
+use bevy_collision::{
+    collider::{Collider, ColliderShapes},
+    contacts::SensorEntered,
+    layers::{CollisionFilter, CollisionLayers},
+};
 use common::{ParticleTypes, PlayerMarker};
 use rand::prelude::*;
 
 use crate::delayed_despawn::DelayedDespawns;
 
+/// players touching this sphere around energy draw it in
+const PICKUP_RADIUS: f32 = 5.0;
+
 pub struct Energy {
     pub amount: f32,
 }
@@ -82,6 +90,17 @@ fn regularily_spawn_energy(
                                 ..Default::default()
                             })
                             .insert(Energy { amount: 10.0 })
+                            .insert(Collider {
+                                collider_shape: ColliderShapes::Sphere {
+                                    radius: PICKUP_RADIUS,
+                                },
+                                local_position: Vec3::ZERO,
+                                filter: CollisionFilter::new(
+                                    CollisionLayers::PICKUP,
+                                    CollisionLayers::PLAYER,
+                                ),
+                                sensor: true,
+                            })
                             .id();
                         despanws_res
                             .despawns
@@ -95,21 +114,21 @@ fn regularily_spawn_energy(
 
 fn draw_in_energy(
     mut commands: Commands,
-    mut players_query: Query<(&mut Energy, &Transform), With<PlayerMarker>>,
-    pickups_query: Query<(Entity, &Energy, &Transform), Without<PlayerMarker>>,
+    mut sensor_events: EventReader<SensorEntered>,
+    mut players_query: Query<&mut Energy, With<PlayerMarker>>,
+    pickups_query: Query<&Energy, Without<PlayerMarker>>,
 ) {
     let mut despawn_entities: Vec<Entity> = Vec::new();
-    for (mut player_energy, player_transform) in players_query.iter_mut() {
-        for (entity, energy, energy_transform) in pickups_query.iter() {
-            if despawn_entities.iter().find(|e| **e == entity).is_none()
-                && player_transform
-                    .translation
-                    .distance_squared(energy_transform.translation)
-                    < 25.0
-            {
-                player_energy.amount += energy.amount;
-                despawn_entities.push(entity);
-            }
+    for event in sensor_events.iter() {
+        if despawn_entities.contains(&event.sensor) {
+            continue;
+        }
+        if let (Ok(energy), Ok(mut player_energy)) = (
+            pickups_query.get(event.sensor),
+            players_query.get_mut(event.entity),
+        ) {
+            player_energy.amount += energy.amount;
+            despawn_entities.push(event.sensor);
         }
     }
 
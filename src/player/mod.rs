@@ -1,10 +1,17 @@
 mod input;
 pub mod model;
+mod tools;
 
 use crate::player::input::publish_player_movements;
 use crate::player::model::ReceivesInput;
+use crate::player::tools::{
+    setup_voxel_highlight, update_voxel_target, use_edit_log, use_voxel_tools,
+};
 use bevy::prelude::*;
-use bevy_collision::collider::{Collider, ColliderShapes};
+use bevy_collision::{
+    collider::{Collider, ColliderShapes},
+    layers::{CollisionFilter, CollisionLayers},
+};
 use common::{Movable, PlayerMarker, PlayerPosition, UnitRotation};
 
 pub struct PlayerPlugin;
@@ -12,7 +19,11 @@ pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_startup_system(player_setup.system())
-            .add_system(publish_player_movements.system());
+            .add_startup_system(setup_voxel_highlight.system())
+            .add_system(publish_player_movements.system())
+            .add_system(update_voxel_target.system())
+            .add_system(use_voxel_tools.system())
+            .add_system(use_edit_log.system());
     }
 }
 
@@ -39,6 +50,8 @@ fn player_setup(
         .insert(Collider {
             collider_shape: ColliderShapes::cube(0.5),
             local_position: Vec3::new(0.0, 0.0, 0.0),
+            filter: CollisionFilter::new(CollisionLayers::PLAYER, CollisionLayers::ALL),
+            sensor: false,
         })
         .insert(ReceivesInput)
         .insert(Movable)
@@ -76,5 +89,7 @@ fn player_setup(
         .insert(Collider {
             collider_shape: ColliderShapes::Sphere { radius: 0.5 },
             local_position: Vec3::new(0.0, 0.0, 0.0),
+            filter: CollisionFilter::default(),
+            sensor: false,
         });
 }
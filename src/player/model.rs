@@ -1 +1,10 @@
+use voxel::access::RaycastHit;
+
 pub struct ReceivesInput;
+
+/// the voxel face the player is looking at within reach of the voxel tools
+pub struct VoxelTarget {
+    pub hit: Option<RaycastHit>,
+}
+
+pub struct VoxelHighlight;
@@ -0,0 +1,141 @@
+use bevy::{app::Events, prelude::*, render::camera::PerspectiveProjection};
+use bevy_collision::{broad_phase::Aabb, collider::Collider};
+use common::PlayerMarker;
+use voxel::{
+    access::VoxelAccess,
+    edit_log::EditLog,
+    model::WorldUpdateEvent,
+    voxel::{Voxel, VoxelDirection, VoxelTypes, HALF_VOXEL_SIZE},
+};
+
+use crate::player::model::{ReceivesInput, VoxelHighlight, VoxelTarget};
+
+// m
+const TOOL_REACH: f32 = 12.0f32;
+const PLACED_VOXEL: VoxelTypes = VoxelTypes::GreyRock1;
+// the highlight floats slightly in front of the face to avoid z fighting
+const HIGHLIGHT_OFFSET: f32 = 0.51f32;
+const EDIT_LOG_PATH: &str = "saves/edit_log.ron";
+// a placed voxel may touch the player, e.g. next to the feet of a player standing on the ground
+const PLACEMENT_TOLERANCE: f32 = 0.01f32;
+
+pub fn setup_voxel_highlight(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(VoxelTarget { hit: None });
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Box::new(1.02, 1.02, 0.02))),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgba(1.0, 1.0, 1.0, 0.4),
+                unlit: true,
+                ..Default::default()
+            }),
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
+            ..Default::default()
+        })
+        .insert(VoxelHighlight);
+}
+
+/// raycasts along the view of the player camera and moves the highlight onto the targeted face
+pub fn update_voxel_target(
+    chunk_access: Res<VoxelAccess>,
+    mut target: ResMut<VoxelTarget>,
+    camera_query: Query<&GlobalTransform, With<PerspectiveProjection>>,
+    mut highlight_query: Query<(&mut Transform, &mut Visible), With<VoxelHighlight>>,
+) {
+    target.hit = camera_query.iter().next().and_then(|camera| {
+        let direction = camera.rotation.mul_vec3(-Vec3::Z);
+        chunk_access.raycast(camera.translation, direction, TOOL_REACH)
+    });
+
+    for (mut transform, mut visible) in highlight_query.iter_mut() {
+        visible.is_visible = target.hit.is_some();
+        if let Some(hit) = target.hit {
+            let [x, y, z] = hit.face.offset();
+            transform.translation =
+                hit.position.to_vec() + Vec3::new(x as f32, y as f32, z as f32) * HIGHLIGHT_OFFSET;
+            transform.rotation = match hit.face {
+                VoxelDirection::UP | VoxelDirection::DOWN => {
+                    Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)
+                }
+                VoxelDirection::LEFT | VoxelDirection::RIGHT => {
+                    Quat::from_rotation_y(std::f32::consts::FRAC_PI_2)
+                }
+                VoxelDirection::FRONT | VoxelDirection::BACK => Quat::IDENTITY,
+            };
+        }
+    }
+}
+
+/// left click removes the targeted voxel, right click places a voxel in front of the targeted face
+/// unless the voxel would overlap the player
+pub fn use_voxel_tools(
+    buttons: Res<Input<MouseButton>>,
+    target: Res<VoxelTarget>,
+    input_receiver_query: Query<&ReceivesInput>,
+    player_query: Query<(&Collider, &Transform), With<PlayerMarker>>,
+    mut update_events: ResMut<Events<WorldUpdateEvent>>,
+) {
+    if input_receiver_query.iter().next().is_none() {
+        return;
+    }
+    if let Some(hit) = target.hit {
+        if buttons.just_pressed(MouseButton::Left) {
+            let position = hit.position;
+            update_events.send(
+                WorldUpdateEvent::deletion(move |_: &VoxelAccess| vec![position], false)
+                    .by_player(),
+            );
+        } else if buttons.just_pressed(MouseButton::Right) {
+            let voxel = Voxel {
+                position: hit.position.in_direction(hit.face),
+                typ: PLACED_VOXEL,
+            };
+            let center = voxel.position.to_vec();
+            let voxel_box = Aabb {
+                min: center - Vec3::splat(HALF_VOXEL_SIZE - PLACEMENT_TOLERANCE),
+                max: center + Vec3::splat(HALF_VOXEL_SIZE - PLACEMENT_TOLERANCE),
+            };
+            if player_query.iter().any(|(collider, transform)| {
+                collider
+                    .aabb(&transform.compute_matrix())
+                    .overlaps(&voxel_box)
+            }) {
+                return;
+            }
+            update_events.send(
+                WorldUpdateEvent::setting(move |_: &VoxelAccess| vec![voxel.clone()]).by_player(),
+            );
+        }
+    }
+}
+
+/// Z undoes the last world edit, Y redoes it, F9 dumps the edit log
+pub fn use_edit_log(
+    keys: Res<Input<KeyCode>>,
+    mut edit_log: ResMut<EditLog>,
+    mut update_events: ResMut<Events<WorldUpdateEvent>>,
+) {
+    let event = if keys.just_pressed(KeyCode::Z) {
+        edit_log.undo()
+    } else if keys.just_pressed(KeyCode::Y) {
+        edit_log.redo()
+    } else {
+        None
+    };
+    if let Some(event) = event {
+        update_events.send(event);
+    }
+    if keys.just_pressed(KeyCode::F9) {
+        match edit_log.dump(EDIT_LOG_PATH) {
+            Ok(()) => info!("Wrote the edit log to {}", EDIT_LOG_PATH),
+            Err(e) => error!("Could not write the edit log to {}: {}", EDIT_LOG_PATH, e),
+        }
+    }
+}
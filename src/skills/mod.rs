@@ -0,0 +1,224 @@
+pub mod model;
+
+use bevy::{app::Events, prelude::*, render::camera::PerspectiveProjection};
+use bevy_collision::{
+    contacts::{ContactPartner, Contacts},
+    rigid_body::{PhysicsSettings, RigidBody},
+};
+use common::{MoveEvent, PlayerMarker};
+use strum::IntoEnumIterator;
+use voxel::{access::VoxelAccess, FreeFloatingVoxel};
+
+use crate::{
+    ai::model::NPC,
+    pickups::Energy,
+    player::model::ReceivesInput,
+    skills::model::{Lashing, LashingPull, Skill, SkillCooldowns},
+};
+
+// m
+const SKILL_RANGE: f32 = 30.0f32;
+// m, how far from the looking ray a lash target may be
+const LASH_AIM_TOLERANCE: f32 = 1.5f32;
+// m
+const PULL_RADIUS: f32 = 10.0f32;
+// m/s², gravity of lashed entities, stronger than the regular gravity
+const LASHING_ACCELERATION: f32 = 20.0f32;
+// s, afterwards the regular gravity applies again
+const LASHING_DURATION: f32 = 10.0f32;
+
+pub struct SkillPlugin;
+
+impl Plugin for SkillPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(add_skill_cooldowns.system())
+            .add_system(use_skills.system())
+            .add_system(apply_lashings.system());
+    }
+}
+
+fn add_skill_cooldowns(
+    mut commands: Commands,
+    player_query: Query<Entity, (With<ReceivesInput>, Without<SkillCooldowns>)>,
+) {
+    for entity in player_query.iter() {
+        commands.entity(entity).insert(SkillCooldowns::default());
+    }
+}
+
+fn use_skills(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    chunk_access: Res<VoxelAccess>,
+    mut player_query: Query<
+        (Entity, &Transform, &mut SkillCooldowns, &mut Energy),
+        With<ReceivesInput>,
+    >,
+    camera_query: Query<&GlobalTransform, With<PerspectiveProjection>>,
+    targets_query: Query<
+        (Entity, &Transform),
+        (
+            Or<(With<NPC>, With<FreeFloatingVoxel>)>,
+            Without<ReceivesInput>,
+        ),
+    >,
+) {
+    let camera = match camera_query.iter().next() {
+        Some(camera) => camera,
+        None => return,
+    };
+    let origin = camera.translation;
+    let looking = camera.rotation.mul_vec3(-Vec3::Z);
+
+    // the lashed entities fall towards the targeted surface
+    let surface = chunk_access
+        .raycast(origin, looking, SKILL_RANGE)
+        .map(|hit| hit.point(origin, looking));
+
+    for (player, player_transform, mut cooldowns, mut energy) in player_query.iter_mut() {
+        cooldowns.tick(time.delta_seconds());
+        for skill in Skill::iter() {
+            if !keys.just_pressed(skill.key()) || !cooldowns.ready(skill) {
+                continue;
+            }
+            let lashed: Vec<(Entity, LashingPull)> = match skill {
+                Skill::SelfLashing => surface
+                    .map(|point| {
+                        let direction = point - player_transform.translation;
+                        vec![(player, LashingPull::Direction(direction))]
+                    })
+                    .unwrap_or_default(),
+                Skill::Lash => surface
+                    .and_then(|point| {
+                        closest_to_ray(&targets_query, origin, looking).map(|(target, position)| {
+                            vec![(target, LashingPull::Direction(point - position))]
+                        })
+                    })
+                    .unwrap_or_default(),
+                Skill::Pull => surface
+                    .map(|point| {
+                        targets_query
+                            .iter()
+                            .filter(|(_, transform)| {
+                                transform.translation.distance_squared(point)
+                                    < PULL_RADIUS * PULL_RADIUS
+                            })
+                            .map(|(entity, _)| (entity, LashingPull::Point(point)))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            };
+            // nothing is targeted, the skill is not used
+            if lashed.is_empty() || !cooldowns.try_use(skill, &mut energy) {
+                continue;
+            }
+            for (entity, pull) in lashed {
+                commands.entity(entity).insert(Lashing::new(
+                    pull,
+                    LASHING_ACCELERATION,
+                    LASHING_DURATION,
+                ));
+            }
+        }
+    }
+}
+
+/// the target closest to the looking ray within range and its position
+fn closest_to_ray(
+    targets_query: &Query<
+        (Entity, &Transform),
+        (
+            Or<(With<NPC>, With<FreeFloatingVoxel>)>,
+            Without<ReceivesInput>,
+        ),
+    >,
+    origin: Vec3,
+    direction: Vec3,
+) -> Option<(Entity, Vec3)> {
+    targets_query
+        .iter()
+        .filter_map(|(entity, transform)| {
+            let to_target = transform.translation - origin;
+            let along = to_target.dot(direction);
+            if along < 0.0 || along > SKILL_RANGE {
+                return None;
+            }
+            let off_ray = (to_target - direction * along).length();
+            if off_ray < LASH_AIM_TOLERANCE {
+                Some((entity, transform.translation, off_ray))
+            } else {
+                None
+            }
+        })
+        .min_by(|(_, _, a), (_, _, b)| a.partial_cmp(b).unwrap())
+        .map(|(entity, position, _)| (entity, position))
+}
+
+/*
+Rigid bodies fall towards the surface through their gravity, so the integrator clamps their speed and they keep their
+velocity when the lashing ends. Other entities are moved by MoveEvents with the velocity of the lashing, which is
+clamped the same way, stopped by the terrain and slowed down after the lashing instead of dropping to zero.
+ */
+fn apply_lashings(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<PhysicsSettings>,
+    contacts: Res<Contacts>,
+    mut movement_events: ResMut<Events<MoveEvent>>,
+    mut lashed_query: Query<(
+        Entity,
+        &mut Lashing,
+        &Transform,
+        Option<&mut RigidBody>,
+        Option<&PlayerMarker>,
+    )>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut lashing, transform, body, player) in lashed_query.iter_mut() {
+        let gravity = if lashing.timer.tick(time.delta()).finished() {
+            None
+        } else {
+            lashing.gravity(transform.translation)
+        };
+        if let Some(mut body) = body {
+            body.gravity = gravity;
+            if gravity.is_none() {
+                commands.entity(entity).remove::<Lashing>();
+            }
+            continue;
+        }
+        let mut velocity = lashing.velocity;
+        match gravity {
+            Some(gravity) => velocity += gravity * delta,
+            None => {
+                let speed = velocity.length();
+                let braking = lashing.acceleration * delta;
+                if speed <= braking {
+                    commands.entity(entity).remove::<Lashing>();
+                    continue;
+                }
+                velocity -= velocity / speed * braking;
+            }
+        }
+        if velocity.length() > settings.max_speed {
+            velocity = velocity.normalize() * settings.max_speed;
+        }
+        // the entity comes to rest on the surface instead of pressing into it
+        for contact in contacts.of(entity) {
+            let into_terrain = velocity.dot(contact.normal);
+            if contact.other == ContactPartner::Terrain && into_terrain < 0.0 {
+                velocity -= contact.normal * into_terrain;
+            }
+        }
+        lashing.velocity = velocity;
+        // MoveEvent translations are relative to the rotation of the entity
+        let translation_offset = transform.rotation.conjugate().mul_vec3(velocity * delta);
+        movement_events.send(MoveEvent {
+            rotation_offset: Vec3::ZERO,
+            translation_offset,
+            entity,
+            is_player: player.is_some(),
+        });
+    }
+}
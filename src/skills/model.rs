@@ -0,0 +1,168 @@
+use ahash::AHashMap;
+use bevy::prelude::*;
+use strum_macros::EnumIter;
+
+use crate::pickups::Energy;
+
+// entities pulled towards a point are released within this distance
+const PULL_STOP_DISTANCE: f32 = 1.0f32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter)]
+pub enum Skill {
+    /// points the gravity of the player at the targeted surface
+    SelfLashing,
+    /// points the gravity of the aimed at npc or free floating voxel at the surface behind it
+    Lash,
+    /// pulls npcs and free floating voxels near the targeted voxel towards it
+    Pull,
+}
+
+impl Skill {
+    pub fn cost(&self) -> f32 {
+        match self {
+            Skill::SelfLashing => 5.0,
+            Skill::Lash => 3.0,
+            Skill::Pull => 10.0,
+        }
+    }
+
+    /// s
+    pub fn cooldown(&self) -> f32 {
+        match self {
+            Skill::SelfLashing => 1.0,
+            Skill::Lash => 0.5,
+            Skill::Pull => 5.0,
+        }
+    }
+
+    pub fn key(&self) -> KeyCode {
+        match self {
+            Skill::SelfLashing => KeyCode::Key1,
+            Skill::Lash => KeyCode::Key2,
+            Skill::Pull => KeyCode::Key3,
+        }
+    }
+}
+
+/// seconds until the skills can be used again
+#[derive(Default)]
+pub struct SkillCooldowns {
+    remaining: AHashMap<Skill, f32>,
+}
+
+impl SkillCooldowns {
+    pub fn tick(&mut self, delta: f32) {
+        for remaining in self.remaining.values_mut() {
+            *remaining -= delta;
+        }
+        self.remaining.retain(|_, remaining| *remaining > 0.0);
+    }
+
+    pub fn ready(&self, skill: Skill) -> bool {
+        !self.remaining.contains_key(&skill)
+    }
+
+    /// pays the cost and starts the cooldown if the skill is ready and there is enough energy
+    pub fn try_use(&mut self, skill: Skill, energy: &mut Energy) -> bool {
+        if !self.ready(skill) || energy.amount < skill.cost() {
+            return false;
+        }
+        energy.amount -= skill.cost();
+        self.remaining.insert(skill, skill.cooldown());
+        true
+    }
+}
+
+/// where the gravity of a lashed entity points
+#[derive(Clone, Copy, Debug)]
+pub enum LashingPull {
+    /// towards a surface, fixed when the lashing starts
+    Direction(Vec3),
+    /// towards a point, the lashing ends once the entity reached it
+    Point(Vec3),
+}
+
+/// Replaces the gravity of an entity until the timer finishes.
+/// Rigid bodies are lashed through RigidBody::gravity, other entities are moved by MoveEvents.
+pub struct Lashing {
+    pub pull: LashingPull,
+    /// m/s²
+    pub acceleration: f32,
+    /// m/s, only used for entities without a rigid body
+    pub velocity: Vec3,
+    pub timer: Timer,
+}
+
+impl Lashing {
+    pub fn new(pull: LashingPull, acceleration: f32, seconds: f32) -> Lashing {
+        Lashing {
+            pull,
+            acceleration,
+            velocity: Vec3::ZERO,
+            timer: Timer::from_seconds(seconds, false),
+        }
+    }
+
+    /// m/s², None once a pulled entity reached the point
+    pub fn gravity(&self, position: Vec3) -> Option<Vec3> {
+        let direction = match self.pull {
+            LashingPull::Direction(direction) => direction,
+            LashingPull::Point(point) => {
+                let to_point = point - position;
+                if to_point.length() < PULL_STOP_DISTANCE {
+                    return None;
+                }
+                to_point
+            }
+        };
+        if direction.length_squared() == 0.0 {
+            return None;
+        }
+        Some(direction.normalize() * self.acceleration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pickups::Energy;
+
+    use super::{Skill, SkillCooldowns};
+
+    #[test]
+    fn using_a_skill_pays_its_cost() {
+        let mut cooldowns = SkillCooldowns::default();
+        let mut energy = Energy { amount: 20.0 };
+
+        assert!(cooldowns.try_use(Skill::Pull, &mut energy));
+        assert_eq!(energy.amount, 20.0 - Skill::Pull.cost());
+        assert!(!cooldowns.ready(Skill::Pull));
+        assert!(cooldowns.ready(Skill::Lash));
+    }
+
+    #[test]
+    fn skills_need_enough_energy() {
+        let mut cooldowns = SkillCooldowns::default();
+        let mut energy = Energy {
+            amount: Skill::Pull.cost() - 1.0,
+        };
+
+        assert!(!cooldowns.try_use(Skill::Pull, &mut energy));
+        assert_eq!(energy.amount, Skill::Pull.cost() - 1.0);
+        assert!(cooldowns.ready(Skill::Pull));
+    }
+
+    #[test]
+    fn skills_are_ready_again_after_the_cooldown() {
+        let mut cooldowns = SkillCooldowns::default();
+        let mut energy = Energy { amount: 100.0 };
+        assert!(cooldowns.try_use(Skill::Lash, &mut energy));
+
+        cooldowns.tick(Skill::Lash.cooldown() / 2.0);
+        assert!(!cooldowns.try_use(Skill::Lash, &mut energy));
+        assert_eq!(energy.amount, 100.0 - Skill::Lash.cost());
+
+        cooldowns.tick(Skill::Lash.cooldown());
+        assert!(cooldowns.try_use(Skill::Lash, &mut energy));
+        assert_eq!(energy.amount, 100.0 - 2.0 * Skill::Lash.cost());
+    }
+}
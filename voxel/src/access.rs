@@ -1,17 +1,34 @@
 use ahash::AHashMap;
-use bevy::prelude::Entity;
+use bevy::prelude::{Entity, Vec3};
 use std::collections::hash_map::{Iter, IterMut};
 
 use super::{
     boundaries::ChunkBoundaries,
     chunk::VoxelChunk,
-    voxel::{VoxelPosition, VoxelTypes},
+    voxel::{VoxelDirection, VoxelPosition, VoxelTypes, HALF_VOXEL_SIZE, VOXEL_SIZE},
 };
 
 pub struct VoxelAccess {
     chunks: AHashMap<ChunkBoundaries, (Entity, VoxelChunk)>,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RaycastHit {
+    pub position: VoxelPosition,
+    /// face of the hit voxel the ray entered through
+    pub face: VoxelDirection,
+    /// distance from the ray origin to the entry point on the face
+    pub distance: f32,
+    pub typ: VoxelTypes,
+}
+
+impl RaycastHit {
+    /// where the ray entered the voxel
+    pub fn point(&self, origin: Vec3, direction: Vec3) -> Vec3 {
+        origin + direction.normalize() * self.distance
+    }
+}
+
 impl VoxelAccess {
     pub fn get_chunk_containing(&self, position: VoxelPosition) -> Option<&VoxelChunk> {
         let boundary = ChunkBoundaries::aligned(position);
@@ -38,6 +55,10 @@ impl VoxelAccess {
         self.chunks.get(boundary).map(|(_, c)| c)
     }
 
+    pub fn get_chunk_mut(&mut self, boundary: &ChunkBoundaries) -> Option<&mut VoxelChunk> {
+        self.chunks.get_mut(boundary).map(|(_, c)| c)
+    }
+
     pub fn get_chunk_entity(&self, boundary: &ChunkBoundaries) -> Option<&(Entity, VoxelChunk)> {
         self.chunks.get(boundary)
     }
@@ -46,6 +67,14 @@ impl VoxelAccess {
         self.chunks.insert(boundary, (entity, chunk));
     }
 
+    pub fn remove_chunk(&mut self, boundary: &ChunkBoundaries) -> Option<(Entity, VoxelChunk)> {
+        self.chunks.remove(boundary)
+    }
+
+    pub fn contains_entity(&self, entity: Entity) -> bool {
+        self.chunks.values().any(|(e, _)| *e == entity)
+    }
+
     pub fn new() -> VoxelAccess {
         VoxelAccess {
             chunks: AHashMap::new(),
@@ -64,4 +93,167 @@ impl VoxelAccess {
         self.get_chunk_containing(position)
             .and_then(|c| c.get(&position))
     }
+
+    /// First voxel along the ray within max_distance, unloaded chunks are treated as empty.
+    /// Walks the voxel grid cell by cell (DDA), so no voxel on the way can be skipped.
+    /// A ray starting inside a voxel hits it at distance 0 on the face opposing the direction.
+    /// An infinite max_distance returns None, as the walk through empty space would never end.
+    pub fn raycast(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<RaycastHit> {
+        if direction.length_squared() == 0.0
+            || !direction.is_finite()
+            || !origin.is_finite()
+            || !max_distance.is_finite()
+        {
+            return None;
+        }
+        let direction = direction.normalize();
+        // in voxel units, shifted so voxel p spans [p, p + 1) instead of p +- 0.5
+        let start = (origin + Vec3::splat(HALF_VOXEL_SIZE)) / VOXEL_SIZE;
+        let start = [start.x, start.y, start.z];
+        let direction = [direction.x, direction.y, direction.z];
+        let max_distance = max_distance / VOXEL_SIZE;
+
+        let mut cell = [
+            start[0].floor() as i32,
+            start[1].floor() as i32,
+            start[2].floor() as i32,
+        ];
+        let mut step = [0; 3];
+        let mut t_max = [f32::INFINITY; 3];
+        let mut t_delta = [f32::INFINITY; 3];
+        for axis in 0..3 {
+            if direction[axis] > 0.0 {
+                step[axis] = 1;
+                t_max[axis] = (cell[axis] as f32 + 1.0 - start[axis]) / direction[axis];
+            } else if direction[axis] < 0.0 {
+                step[axis] = -1;
+                t_max[axis] = (start[axis] - cell[axis] as f32) / -direction[axis];
+            }
+            if step[axis] != 0 {
+                t_delta[axis] = 1.0 / direction[axis].abs();
+            }
+        }
+
+        let dominant = (0..3)
+            .max_by(|a, b| {
+                direction[*a]
+                    .abs()
+                    .partial_cmp(&direction[*b].abs())
+                    .unwrap()
+            })
+            .unwrap();
+        let mut face = VoxelDirection::from_axis(dominant, direction[dominant] < 0.0);
+        let mut distance = 0.0;
+        loop {
+            let position = VoxelPosition::new(cell[0], cell[1], cell[2]);
+            if let Some(typ) = self.get_voxel(position) {
+                return Some(RaycastHit {
+                    position,
+                    face,
+                    distance: distance * VOXEL_SIZE,
+                    typ,
+                });
+            }
+
+            let axis = if t_max[0] < t_max[1] {
+                if t_max[0] < t_max[2] {
+                    0
+                } else {
+                    2
+                }
+            } else if t_max[1] < t_max[2] {
+                1
+            } else {
+                2
+            };
+            distance = t_max[axis];
+            if distance > max_distance {
+                return None;
+            }
+            cell[axis] += step[axis];
+            t_max[axis] += t_delta[axis];
+            face = VoxelDirection::from_axis(axis, step[axis] < 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{Entity, Vec3};
+
+    use crate::{
+        boundaries::ChunkBoundaries,
+        chunk::VoxelChunk,
+        voxel::{Voxel, VoxelDirection, VoxelPosition, VoxelTypes},
+    };
+
+    use super::VoxelAccess;
+
+    fn access_with(voxels: &[Voxel]) -> VoxelAccess {
+        let mut access = VoxelAccess::new();
+        for (i, voxel) in voxels.iter().enumerate() {
+            let boundary = ChunkBoundaries::aligned(voxel.position);
+            if access.get_chunk(&boundary).is_none() {
+                access.add_chunk(boundary, Entity::new(i as u32), VoxelChunk::empty(boundary));
+            }
+            access
+                .get_chunk_containing_mut(voxel.position)
+                .unwrap()
+                .set(voxel.clone());
+        }
+        access
+    }
+
+    #[test]
+    fn raycast_hits_the_first_voxel_across_chunks() {
+        let access = access_with(&[
+            Voxel::new(70, 0, 0, VoxelTypes::Moss),
+            Voxel::new(80, 0, 0, VoxelTypes::Snow),
+        ]);
+
+        let hit = access
+            .raycast(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 100.0)
+            .unwrap();
+
+        assert_eq!(hit.position, VoxelPosition::new(70, 0, 0));
+        assert_eq!(hit.face, VoxelDirection::LEFT);
+        assert_eq!(hit.typ, VoxelTypes::Moss);
+        assert!((hit.distance - 69.5).abs() < 1e-4);
+        assert!(access
+            .raycast(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 69.0)
+            .is_none());
+    }
+
+    #[test]
+    fn raycast_reports_the_entered_face() {
+        let access = access_with(&[Voxel::new(3, -2, 5, VoxelTypes::GreyRock1)]);
+        let target = Vec3::new(3.0, -2.0, 5.0);
+        for (offset, face) in [
+            (Vec3::new(0.0, 4.0, 0.3), VoxelDirection::UP),
+            (Vec3::new(0.2, -4.0, 0.0), VoxelDirection::DOWN),
+            (Vec3::new(-4.0, 0.1, 0.0), VoxelDirection::LEFT),
+            (Vec3::new(4.0, 0.0, -0.2), VoxelDirection::RIGHT),
+            (Vec3::new(0.0, 0.3, -4.0), VoxelDirection::FRONT),
+            (Vec3::new(0.1, 0.0, 4.0), VoxelDirection::BACK),
+        ]
+        .iter()
+        {
+            let hit = access
+                .raycast(target + *offset, -*offset, 10.0)
+                .expect("the voxel is in range");
+            assert_eq!(hit.position, VoxelPosition::new(3, -2, 5));
+            assert_eq!(hit.face, *face);
+        }
+    }
+
+    #[test]
+    fn raycast_into_empty_space_misses() {
+        let access = access_with(&[Voxel::new(3, -2, 5, VoxelTypes::GreyRock1)]);
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+        let direction = Vec3::new(0.0, 1.0, 0.3);
+
+        assert!(access.raycast(origin, direction, 200.0).is_none());
+        assert!(access.raycast(origin, direction, f32::INFINITY).is_none());
+        assert!(access.raycast(origin, direction, f32::NAN).is_none());
+    }
 }
@@ -1,4 +1,9 @@
-use crate::voxel::{Voxel, VoxelPosition};
+use std::mem::size_of;
+
+use crate::{
+    storage::{VoxelStorage, VOXELS_PER_CHUNK},
+    voxel::{Voxel, VoxelPosition},
+};
 
 use super::{
     boundaries::{ChunkBoundaries, CHUNK_SIZE},
@@ -7,19 +12,22 @@ use super::{
 
 #[derive(Debug, Clone)]
 pub struct VoxelChunk {
-    voxels: Vec<Option<VoxelTypes>>,
+    voxels: VoxelStorage,
     pub count: usize,
     pub lod: i32,
     pub boundary: ChunkBoundaries,
+    /// set when the chunk was edited after generation and has not been written to disk yet
+    pub dirty: bool,
 }
 
 impl VoxelChunk {
     pub fn empty(boundary: ChunkBoundaries) -> VoxelChunk {
         VoxelChunk {
-            voxels: vec![],
+            voxels: VoxelStorage::Uniform(None),
             count: 0,
             lod: 1,
             boundary,
+            dirty: false,
         }
     }
 
@@ -28,12 +36,15 @@ impl VoxelChunk {
         A: Fn(&Voxel) -> bool,
     {
         let mut voxels = Vec::with_capacity(self.count);
+        if self.count == 0 {
+            return voxels;
+        }
 
-        for (i, voxel_type_o) in self.voxels.iter().enumerate() {
-            if let Some(v) = voxel_type_o {
+        for i in 0..VOXELS_PER_CHUNK {
+            if let Some(typ) = self.voxels.get(i) {
                 let voxel = Voxel {
                     position: self.index_to_coord(i),
-                    typ: v.clone(),
+                    typ,
                 };
                 if f(&voxel) {
                     voxels.push(voxel);
@@ -44,18 +55,16 @@ impl VoxelChunk {
     }
 
     pub fn get_voxels(&self) -> Vec<Voxel> {
-        let mut voxels = Vec::with_capacity(self.count);
+        self.filter(|_| true)
+    }
 
-        for (i, voxel_type_o) in self.voxels.iter().enumerate() {
-            if let Some(v) = voxel_type_o {
-                let voxel = Voxel {
-                    position: self.index_to_coord(i),
-                    typ: v.clone(),
-                };
-                voxels.push(voxel);
-            }
-        }
-        voxels
+    /// bytes used by this chunk, including the voxel storage
+    pub fn memory_usage(&self) -> usize {
+        size_of::<VoxelChunk>() + self.voxels.heap_size()
+    }
+
+    pub fn is_uniform(&self) -> bool {
+        self.voxels.is_uniform()
     }
 
     fn index_to_coord(&self, i: usize) -> VoxelPosition {
@@ -64,7 +73,7 @@ impl VoxelChunk {
         let x = i as i32 % CHUNK_SIZE;
         VoxelPosition {
             x: x + self.boundary.min[0],
-            y: y + self.boundary.min[2],
+            y: y + self.boundary.min[1],
             z: z + self.boundary.min[2],
         }
     }
@@ -85,15 +94,8 @@ impl VoxelChunk {
         }
 
         let i = self.get_vector_position(&voxel.position);
-        if self.count == 0 {
-            self.voxels = vec![None; (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize];
-            self.count = 1;
-            self.voxels[i] = Some(voxel.typ);
-        } else {
-            if self.voxels[i].is_none() {
-                self.count += 1;
-            }
-            self.voxels[i] = Some(voxel.typ);
+        if self.voxels.set(i, Some(voxel.typ)).is_none() {
+            self.count += 1;
         }
     }
 
@@ -102,13 +104,10 @@ impl VoxelChunk {
             None
         } else {
             let i = self.get_vector_position(&position);
-            if let Some(typ) = self.voxels[i] {
-                self.voxels[i] = None;
+            self.voxels.set(i, None).map(|typ| {
                 self.count -= 1;
-                Some(Voxel { position, typ })
-            } else {
-                None
-            }
+                Voxel { position, typ }
+            })
         }
     }
 
@@ -119,16 +118,7 @@ impl VoxelChunk {
                 position, self.boundary
             );
         }
-        if self.count == 0 {
-            None
-        } else {
-            let i = self.get_vector_position(&position);
-            if let Some(typ) = self.voxels[i] {
-                Some(typ)
-            } else {
-                None
-            }
-        }
+        self.voxels.get(self.get_vector_position(&position))
     }
 }
 
@@ -225,6 +215,63 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_uniform_chunk_storage() {
+        let boundaries = ChunkBoundaries::aligned(VoxelPosition { x: 0, y: 0, z: 0 });
+        let mut chunk = VoxelChunk::empty(boundaries);
+        let empty_memory = chunk.memory_usage();
+        assert!(chunk.is_uniform());
+
+        let mixed = Voxel::new(5, 5, 5, VoxelTypes::Snow);
+        chunk.set(mixed.clone());
+        assert!(!chunk.is_uniform());
+        assert!(chunk.memory_usage() > empty_memory);
+
+        for (x, y, z) in iproduct!(0..CHUNK_SIZE, 0..CHUNK_SIZE, 0..CHUNK_SIZE) {
+            chunk.set(Voxel::new(x, y, z, VoxelTypes::GreyRock1));
+        }
+        assert!(chunk.is_uniform());
+        assert_eq!(chunk.memory_usage(), empty_memory);
+        assert_eq!(chunk.count, (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize);
+
+        chunk.set(mixed.clone());
+        assert_eq!(chunk.remove(mixed.position), Some(mixed.clone()));
+        assert_eq!(chunk.get(&mixed.position), None);
+        assert_eq!(
+            chunk.get(&VoxelPosition { x: 5, y: 5, z: 6 }),
+            Some(VoxelTypes::GreyRock1)
+        );
+    }
+
+    #[test]
+    fn test_palette_growth() {
+        let boundaries = ChunkBoundaries::aligned(VoxelPosition { x: -1, y: 70, z: 0 });
+        let mut chunk = VoxelChunk::empty(boundaries);
+        let types = [
+            VoxelTypes::Moss,
+            VoxelTypes::DarkRock1,
+            VoxelTypes::GreyRock1,
+            VoxelTypes::GreyRock2,
+            VoxelTypes::BrownRock,
+            VoxelTypes::DarkRock2,
+            VoxelTypes::GroundRock1,
+            VoxelTypes::Snow,
+        ];
+        for (i, typ) in types.iter().enumerate() {
+            chunk.set(Voxel::new(-64 + i as i32, 64 + i as i32, i as i32, *typ));
+        }
+        for (i, typ) in types.iter().enumerate() {
+            assert_eq!(
+                chunk.get(&VoxelPosition::new(-64 + i as i32, 64 + i as i32, i as i32)),
+                Some(*typ)
+            );
+        }
+        assert_eq!(
+            chunk.get_voxels().iter().map(|v| v.typ).collect::<Vec<_>>(),
+            types.to_vec()
+        );
+    }
+
     #[test]
     fn boundary_over_position_and_contains_consistency() {
         let position = VoxelPosition {
@@ -4,11 +4,30 @@ use bevy::{
 };
 use std::borrow::Cow;
 
-use super::{chunk::VoxelChunk, lod::combine_voxels, voxel::VoxelTypes};
+use super::{
+    chunk::VoxelChunk, greedy_mesh::greedy_mesh, lod::combine_voxels, neighbours::ChunkNeighbours,
+    voxel::VoxelTypes,
+};
 
-impl From<&VoxelChunk> for Mesh {
-    fn from(chunk: &VoxelChunk) -> Self {
-        let faces = combine_voxels(chunk);
+/// Selects how chunk meshes are built.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChunkMesher {
+    /// one quad per visible face of each lod cell
+    Faces,
+    /// coplanar faces of the same type are merged into larger quads with tiled uvs
+    Greedy,
+}
+
+impl ChunkMesher {
+    pub fn mesh(&self, chunk: &VoxelChunk, neighbours: &ChunkNeighbours) -> Mesh {
+        match self {
+            ChunkMesher::Faces => ChunkMesher::face_mesh(chunk, neighbours),
+            ChunkMesher::Greedy => greedy_mesh(chunk, neighbours),
+        }
+    }
+
+    pub fn face_mesh(chunk: &VoxelChunk, neighbours: &ChunkNeighbours) -> Mesh {
+        let faces = combine_voxels(chunk, neighbours);
 
         let vertices_count = (faces.len()) * 4;
         let mut indices: Vec<u32> = Vec::with_capacity(vertices_count * 6);
@@ -277,7 +296,13 @@ impl From<&VoxelChunk> for Mesh {
     }
 }
 
-fn uvs_from_typ(typ: &VoxelTypes) -> (f32, f32, f32, f32) {
+impl From<&VoxelChunk> for Mesh {
+    fn from(chunk: &VoxelChunk) -> Self {
+        ChunkMesher::face_mesh(chunk, &ChunkNeighbours::none(chunk.boundary))
+    }
+}
+
+pub(crate) fn uvs_from_typ(typ: &VoxelTypes) -> (f32, f32, f32, f32) {
     match typ {
         VoxelTypes::DarkRock1 => (0.0, 0.125, 0.0, 1.0),
         VoxelTypes::Moss => (0.125, 0.25, 0.0, 1.0),
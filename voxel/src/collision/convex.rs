@@ -0,0 +1,44 @@
+use bevy::prelude::*;
+use bevy_collision::shapes::ConvexPart;
+
+use crate::{
+    access::VoxelAccess,
+    voxel::{world_2_voxel_space, VoxelPosition, HALF_VOXEL_SIZE},
+};
+
+/// moves the parts out of the terrain, like for cuboids the deepest overlapping voxel decides
+pub fn collision_depth_convex(voxel_access: &VoxelAccess, parts: &[ConvexPart]) -> Vec3 {
+    let mut deepest = Vec3::ZERO;
+    for part in parts.iter() {
+        let aabb = part.aabb();
+        for potential_x in world_2_voxel_space(aabb.min.x) - 1..world_2_voxel_space(aabb.max.x) + 1
+        {
+            for potential_y in
+                world_2_voxel_space(aabb.min.y) - 1..world_2_voxel_space(aabb.max.y) + 1
+            {
+                for potential_z in
+                    world_2_voxel_space(aabb.min.z) - 1..world_2_voxel_space(aabb.max.z) + 1
+                {
+                    let position = VoxelPosition {
+                        x: potential_x,
+                        y: potential_y,
+                        z: potential_z,
+                    };
+                    if let Some(chunk) = voxel_access.get_chunk_containing(position) {
+                        if chunk.get(&position).is_none() {
+                            continue;
+                        }
+                        let voxel =
+                            ConvexPart::cuboid(position.to_vec(), Vec3::splat(HALF_VOXEL_SIZE));
+                        if let Some(penetration) = part.penetration(&voxel) {
+                            if penetration.length_squared() > deepest.length_squared() {
+                                deepest = penetration;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    -deepest
+}
@@ -1,3 +1,4 @@
+mod convex;
 mod cuboid;
 pub mod systems;
 #[cfg(test)]
@@ -1,16 +1,24 @@
 use bevy::prelude::*;
 
 use crate::{access::VoxelAccess, voxel::VoxelPosition};
-use bevy_collision::collider::{Collider, ColliderShapes};
+use bevy_collision::{
+    collider::{Collider, ColliderShapes},
+    contacts::{Contact, ContactPartner, Contacts},
+    rigid_body::{contact_impulse, RigidBody},
+};
 
 use super::super::voxel::{world_2_voxel_space, VoxelBox};
-use super::cuboid::collision_depth_cubiod;
+use super::{convex::collision_depth_convex, cuboid::collision_depth_cubiod};
 
 pub fn terrain_collision_system(
     voxel_access: Res<VoxelAccess>,
-    mut movable_colliders_query: Query<(&mut Transform, &Collider)>,
+    mut contacts: ResMut<Contacts>,
+    mut movable_colliders_query: Query<(Entity, &mut Transform, &Collider, Option<&mut RigidBody>)>,
 ) {
-    for (mut transform, collider) in movable_colliders_query.iter_mut() {
+    for (entity, mut transform, collider, body) in movable_colliders_query.iter_mut() {
+        if collider.sensor || !collider.filter.collides_with_terrain() {
+            continue;
+        }
         let mut impulse = Vec3::ZERO;
         let transform_matrix = transform.compute_matrix();
         let transformed_center = transform_matrix.transform_point3(collider.local_position);
@@ -31,8 +39,31 @@ pub fn terrain_collision_system(
                 half_height_y,
                 half_depth_z,
             ),
+            ColliderShapes::Capsule { .. }
+            | ColliderShapes::ConvexHull { .. }
+            | ColliderShapes::Compound { .. } => {
+                collision_depth_convex(&voxel_access, &collider.convex_parts(&transform_matrix))
+            }
         };
+        if impulse.length_squared() > 0.0 {
+            let deepest = collider.support_point(&transform_matrix, -impulse);
+            contacts.add(Contact::new(
+                entity,
+                ContactPartner::Terrain,
+                impulse,
+                deepest + impulse * 0.5,
+            ));
+        }
         transform.translation += impulse;
+        // the terrain is static, rigid bodies bounce off and slide along it
+        if let Some(mut body) = body {
+            if impulse.length_squared() > 0.0 {
+                let normal = impulse.normalize();
+                let radius = collider.collider_shape.bounding_radius();
+                let contact = contact_impulse(&body, None, normal);
+                body.apply_impulse(contact, -normal * radius, radius);
+            }
+        }
     }
 }
 
@@ -0,0 +1,204 @@
+use std::{collections::VecDeque, fs, io, path::Path};
+
+use serde::Serialize;
+
+use crate::{
+    model::{VoxelOperation, WorldUpdateEvent},
+    voxel::{Voxel, VoxelPosition, VoxelTypes},
+};
+
+/// older edits are dropped from the history, so erosion running for hours does not fill the memory
+pub const MAX_LOGGED_EDITS: usize = 10000;
+/// player edits further back can not be undone
+pub const MAX_UNDO_STEPS: usize = 1000;
+
+/// what caused an edit, only player edits are undone
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum EditSource {
+    Player,
+    /// erosion, explosions and voxels falling down or settling
+    World,
+    Undo,
+    Redo,
+}
+
+/// change of a single voxel, None is an empty position
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct VoxelEdit {
+    pub position: VoxelPosition,
+    pub old: Option<VoxelTypes>,
+    pub new: Option<VoxelTypes>,
+}
+
+/// all voxel changes caused by one WorldUpdateEvent
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WorldEdit {
+    pub source: EditSource,
+    pub edits: Vec<VoxelEdit>,
+}
+
+impl WorldEdit {
+    /// operations restoring the state before the edit
+    pub fn undo_operations(&self) -> Vec<VoxelOperation> {
+        self.edits
+            .iter()
+            .rev()
+            .map(|edit| operation(edit.position, edit.old))
+            .collect()
+    }
+
+    pub fn redo_operations(&self) -> Vec<VoxelOperation> {
+        self.edits
+            .iter()
+            .map(|edit| operation(edit.position, edit.new))
+            .collect()
+    }
+}
+
+fn operation(position: VoxelPosition, typ: Option<VoxelTypes>) -> VoxelOperation {
+    match typ {
+        Some(typ) => VoxelOperation::Set(Voxel { position, typ }),
+        None => VoxelOperation::Delete(position),
+    }
+}
+
+/*
+Record of every edit applied to the loaded world with its source, filled by update_world_event_reader.
+Only player edits are kept for undo and redo, which move an edit between the two stacks and return the event
+that reapplies it. The reapplied changes are recorded as Undo or Redo edits, which are not undone themselves.
+Recording a new player edit discards the edits that could be redone.
+ */
+#[derive(Default, Serialize)]
+pub struct EditLog {
+    history: VecDeque<WorldEdit>,
+    #[serde(skip)]
+    done: VecDeque<WorldEdit>,
+    #[serde(skip)]
+    undone: Vec<WorldEdit>,
+}
+
+impl EditLog {
+    pub fn record(&mut self, edit: WorldEdit) {
+        if edit.edits.is_empty() {
+            return;
+        }
+        if edit.source == EditSource::Player {
+            if self.done.len() == MAX_UNDO_STEPS {
+                self.done.pop_front();
+            }
+            self.done.push_back(edit.clone());
+            self.undone.clear();
+        }
+        if self.history.len() == MAX_LOGGED_EDITS {
+            self.history.pop_front();
+        }
+        self.history.push_back(edit);
+    }
+
+    pub fn undo(&mut self) -> Option<WorldUpdateEvent> {
+        let edit = self.done.pop_back()?;
+        let operations = edit.undo_operations();
+        self.undone.push(edit);
+        Some(WorldUpdateEvent::fixed(operations, EditSource::Undo))
+    }
+
+    pub fn redo(&mut self) -> Option<WorldUpdateEvent> {
+        let edit = self.undone.pop()?;
+        let operations = edit.redo_operations();
+        self.done.push_back(edit);
+        Some(WorldUpdateEvent::fixed(operations, EditSource::Redo))
+    }
+
+    /// every recorded edit, oldest first
+    pub fn edits(&self) -> impl Iterator<Item = &WorldEdit> {
+        self.history.iter()
+    }
+
+    /// writes the log as ron for debugging
+    pub fn dump<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let content = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        access::VoxelAccess,
+        model::VoxelOperation,
+        voxel::{Voxel, VoxelPosition, VoxelTypes},
+    };
+
+    use super::{EditLog, EditSource, VoxelEdit, WorldEdit};
+
+    fn edit(x: i32, old: Option<VoxelTypes>, new: Option<VoxelTypes>) -> WorldEdit {
+        WorldEdit {
+            source: EditSource::Player,
+            edits: vec![VoxelEdit {
+                position: VoxelPosition::new(x, 0, 0),
+                old,
+                new,
+            }],
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_restore_the_recorded_types() {
+        let mut log = EditLog::default();
+        log.record(edit(1, None, Some(VoxelTypes::Moss)));
+        log.record(edit(2, Some(VoxelTypes::Moss), Some(VoxelTypes::Snow)));
+        let access = VoxelAccess::new();
+
+        let undo = log.undo().unwrap();
+        assert_eq!(undo.source, EditSource::Undo);
+        assert_eq!(
+            (undo.operations)(&access),
+            vec![VoxelOperation::Set(Voxel::new(2, 0, 0, VoxelTypes::Moss))]
+        );
+        let undo = log.undo().unwrap();
+        assert_eq!(
+            (undo.operations)(&access),
+            vec![VoxelOperation::Delete(VoxelPosition::new(1, 0, 0))]
+        );
+        assert!(log.undo().is_none());
+
+        let redo = log.redo().unwrap();
+        assert_eq!(
+            (redo.operations)(&access),
+            vec![VoxelOperation::Set(Voxel::new(1, 0, 0, VoxelTypes::Moss))]
+        );
+        assert_eq!(log.edits().count(), 2);
+    }
+
+    #[test]
+    fn recording_discards_undone_edits() {
+        let mut log = EditLog::default();
+        log.record(edit(1, None, Some(VoxelTypes::Moss)));
+        log.undo();
+        log.record(edit(3, Some(VoxelTypes::Moss), None));
+
+        assert!(log.redo().is_none());
+        assert_eq!(log.edits().count(), 2);
+    }
+
+    #[test]
+    fn world_edits_are_recorded_but_not_undone() {
+        let mut log = EditLog::default();
+        log.record(edit(1, None, Some(VoxelTypes::Moss)));
+        let mut erosion = edit(2, Some(VoxelTypes::Moss), None);
+        erosion.source = EditSource::World;
+        log.record(erosion.clone());
+
+        assert_eq!(log.edits().last(), Some(&erosion));
+        let undo = log.undo().unwrap();
+        assert_eq!(
+            (undo.operations)(&VoxelAccess::new()),
+            vec![VoxelOperation::Delete(VoxelPosition::new(1, 0, 0))]
+        );
+        assert!(log.undo().is_none());
+    }
+}
@@ -1,5 +1,3 @@
-use std::sync::Arc;
-
 use bevy::{app::Events, prelude::*};
 use common::MoveEvent;
 use common::ParticleTypes;
@@ -39,14 +37,11 @@ pub fn erosion(
                     };
                     let boundaries_clone = boundaries.clone();
                     let pt = particle_type.clone();
-                    let delete = Arc::new(move |chunks: &VoxelAccess| {
+                    let delete = move |chunks: &VoxelAccess| {
                         select_a_highest_voxel(&boundaries_clone, pt, highstorm_center, chunks)
-                    });
+                    };
 
-                    update_events.send(WorldUpdateEvent {
-                        delete,
-                        replace: true,
-                    });
+                    update_events.send(WorldUpdateEvent::deletion(delete, true));
                 }
             }
         }
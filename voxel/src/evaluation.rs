@@ -1,12 +1,25 @@
 use bevy::{app::Events, prelude::*, tasks::AsyncComputeTaskPool};
+use bevy_collision::collider::{Collider, ColliderShapes};
 use common::{PlayerPosition, UnitRotation};
 use flume::{Receiver, Sender};
 
-use crate::{access::VoxelAccess, boundaries::ChunkBoundaries, lod::distance_2_lod};
+use crate::{
+    access::VoxelAccess,
+    boundaries::ChunkBoundaries,
+    chunk_mesh::ChunkMesher,
+    edit_log::{EditLog, EditSource, VoxelEdit, WorldEdit},
+    falling::{debris_filter, floating_voxel_body, FLOATING_VOXEL_COLLIDER_SIZE},
+    integrity::{floating_islands, floating_placed, MAX_ISLAND_SIZE},
+    lod::distance_2_lod,
+    neighbours::{touching_chunks, ChunkNeighbours},
+};
 
 use super::VoxelTexture;
 use crate::{
-    model::{DelayedWorldTransformations, WorldUpdateEvent, WorldUpdateResult},
+    model::{
+        ChunkRemeshRequests, DelayedWorldTransformations, VoxelOperation, WorldUpdateEvent,
+        WorldUpdateResult,
+    },
     FreeFloatingVoxel,
 };
 use ahash::AHashSet;
@@ -43,8 +56,11 @@ pub fn update_world_event_reader(
     tx: Res<Sender<WorldUpdateResult>>,
     mut chunk_access: ResMut<VoxelAccess>,
     player_position: Res<PlayerPosition>,
+    mesher: Res<ChunkMesher>,
+    mut remesh_requests: ResMut<ChunkRemeshRequests>,
+    mut edit_log: ResMut<EditLog>,
 ) {
-    let mut changed = AHashSet::new();
+    let mut changed: AHashSet<ChunkBoundaries> = remesh_requests.boundaries.drain().collect();
 
     for (boundaries, (_, voxel_chunk)) in chunk_access.iter_mut() {
         let center: Vec3 = voxel_chunk.boundary.center().to_vec();
@@ -55,43 +71,118 @@ pub fn update_world_event_reader(
         }
     }
 
-    let mut replaces = Vec::new();
+    let mut detached = Vec::new();
 
     for event in update_events.iter() {
-        let deletes = (event.delete)(&chunk_access);
-        for delete in deletes {
-            if let Some(chunk) = chunk_access.get_chunk_containing_mut(delete) {
-                if let Some(voxel) = chunk.remove(delete) {
-                    if event.replace {
-                        replaces.push(voxel);
+        let mut edit = WorldEdit {
+            source: event.source,
+            edits: Vec::new(),
+        };
+        let mut removed = Vec::new();
+        let mut placed = Vec::new();
+        for operation in (event.operations)(&chunk_access) {
+            let position = operation.position();
+            let chunk = match chunk_access.get_chunk_containing_mut(position) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+            let old = chunk.get(&position);
+            let new = match operation {
+                // writing the type that is already there changes nothing
+                VoxelOperation::Set(voxel) | VoxelOperation::Replace(voxel)
+                    if old == Some(voxel.typ) =>
+                {
+                    continue
+                }
+                VoxelOperation::Set(voxel) => {
+                    let typ = voxel.typ;
+                    chunk.set(voxel);
+                    Some(typ)
+                }
+                VoxelOperation::Replace(voxel) => {
+                    if old.is_none() {
+                        continue;
+                    }
+                    let typ = voxel.typ;
+                    chunk.set(voxel);
+                    Some(typ)
+                }
+                VoxelOperation::Delete(position) => match chunk.remove(position) {
+                    Some(voxel) => {
+                        if event.detach {
+                            detached.push(voxel);
+                        }
+                        None
+                    }
+                    None => continue,
+                },
+            };
+            // only changes between solid and empty affect the faces of the neighbours
+            let solidity_changed = old.is_none() != new.is_none();
+            edit.edits.push(VoxelEdit { position, old, new });
+            chunk.dirty = true;
+            changed.insert(ChunkBoundaries::aligned(position));
+            if solidity_changed {
+                changed.extend(touching_chunks(position));
+            }
+            if new.is_none() {
+                removed.push(position);
+            } else if event.detach_floating {
+                placed.push(position);
+            }
+        }
+        edit_log.record(edit);
+        // whatever is left hanging in the air falls down,
+        // recorded on its own as undoing the edit would restore the voxels while they are falling
+        let mut detachment = WorldEdit {
+            source: EditSource::World,
+            edits: Vec::new(),
+        };
+        let mut islands = floating_islands(&chunk_access, &removed, MAX_ISLAND_SIZE);
+        islands.extend(floating_placed(&chunk_access, &placed, MAX_ISLAND_SIZE));
+        for island in islands {
+            for position in island {
+                if let Some(chunk) = chunk_access.get_chunk_containing_mut(position) {
+                    if let Some(voxel) = chunk.remove(position) {
+                        chunk.dirty = true;
+                        detachment.edits.push(VoxelEdit {
+                            position,
+                            old: Some(voxel.typ),
+                            new: None,
+                        });
+                        detached.push(voxel);
+                        changed.insert(ChunkBoundaries::aligned(position));
+                        changed.extend(touching_chunks(position));
                     }
-                    changed.insert(ChunkBoundaries::aligned(delete));
                 }
             }
         }
+        edit_log.record(detachment);
     }
 
     let mut entity_chunks = Vec::with_capacity(changed.len());
     for boundaries in changed {
         if let Some((entity, chunk)) = chunk_access.get_chunk_entity(&boundaries) {
-            entity_chunks.push((entity.clone(), chunk.clone()));
+            let neighbours = ChunkNeighbours::from_access(&chunk_access, &boundaries);
+            entity_chunks.push((entity.clone(), chunk.clone(), neighbours));
         }
     }
 
     let tx_c = tx.clone();
+    let mesher = *mesher;
     pool.0
         .spawn(async move {
             let entity_mesh: Vec<_> = entity_chunks
                 .into_iter()
-                .map(|(e, chunk)| {
-                    let mesh = Mesh::from(&chunk);
+                .map(|(e, chunk, neighbours)| {
+                    let mesh = mesher.mesh(&chunk, &neighbours);
                     (e, mesh)
                 })
                 .collect();
 
             tx_c.send(WorldUpdateResult {
                 entity_2_mesh: entity_mesh,
-                voxels_to_replace: replaces,
+                voxels_to_replace: detached,
             })
         })
         .detach();
@@ -101,20 +192,23 @@ pub fn update_world_from_channel(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     material: Res<VoxelTexture>,
+    mesher: Res<ChunkMesher>,
     rx: Res<Receiver<WorldUpdateResult>>,
+    chunk_access: Res<VoxelAccess>,
 ) {
     for world_update_result in rx.try_iter() {
         for (entity, mesh) in world_update_result.entity_2_mesh {
+            // the chunk was unloaded while it was remeshed
+            if !chunk_access.contains_entity(entity) {
+                continue;
+            }
             if match mesh.indices().unwrap() {
                 Indices::U16(i) => i.len() != 0,
                 Indices::U32(i) => i.len() != 0,
             } {
-                commands
-                    .entity(entity)
-                    .remove::<(Handle<Mesh>,)>()
-                    .insert(meshes.add(mesh));
+                material.insert_chunk_mesh(&mut commands.entity(entity), &mesher, meshes.add(mesh));
             } else {
-                commands.entity(entity).remove_bundle::<PbrBundle>();
+                material.remove_chunk_mesh(&mut commands.entity(entity), &mesher);
             }
         }
         for voxel in world_update_result.voxels_to_replace {
@@ -127,7 +221,14 @@ pub fn update_world_from_channel(
             };
             commands
                 .spawn_bundle(bundle)
-                .insert(FreeFloatingVoxel)
+                .insert(FreeFloatingVoxel::new(voxel.typ))
+                .insert(Collider {
+                    collider_shape: ColliderShapes::cube(FLOATING_VOXEL_COLLIDER_SIZE),
+                    local_position: Vec3::ZERO,
+                    filter: debris_filter(),
+                    sensor: false,
+                })
+                .insert(floating_voxel_body())
                 .insert(UnitRotation {
                     rotation: Vec3::ZERO,
                 });
@@ -0,0 +1,109 @@
+use bevy::{app::Events, prelude::*};
+use bevy_collision::{
+    layers::{CollisionFilter, CollisionLayers},
+    rigid_body::RigidBody,
+};
+
+use crate::{
+    access::VoxelAccess,
+    model::WorldUpdateEvent,
+    voxel::{Voxel, VoxelPosition, VoxelTypes},
+    FreeFloatingVoxel,
+};
+
+/// voxels are not settled on top of this many voxels above their position
+const MAX_SETTLE_LIFT: i32 = 3;
+/// slightly smaller than a voxel, so detached voxels fit through gaps of one voxel
+pub const FLOATING_VOXEL_COLLIDER_SIZE: f32 = 0.9;
+/// distance below the collider in which terrain counts as ground
+const GROUND_PROBE: f32 = 0.1;
+/// m/s, faster voxels are not resting even if they are on the ground
+const REST_SPEED: f32 = 0.5;
+
+pub struct FloatingVoxelSettings {
+    /// voxels that rested on the ground for this many seconds are put back into the grid, None keeps them as entities
+    pub settle_after: Option<f32>,
+    /// voxels that fell out of the world are despawned
+    pub despawn_below: f32,
+}
+
+impl Default for FloatingVoxelSettings {
+    fn default() -> Self {
+        FloatingVoxelSettings {
+            settle_after: Some(1.0),
+            despawn_below: -512.0,
+        }
+    }
+}
+
+/// rigid body of a voxel detached from the terrain
+pub fn floating_voxel_body() -> RigidBody {
+    RigidBody {
+        restitution: 0.1,
+        friction: 0.8,
+        ..RigidBody::new(1.0)
+    }
+}
+
+/// debris does not push pickups around
+pub fn debris_filter() -> CollisionFilter {
+    CollisionFilter::new(
+        CollisionLayers::DEBRIS,
+        CollisionLayers::ALL.without(CollisionLayers::PICKUP),
+    )
+}
+
+/*
+Free floating voxels are rigid bodies, they fall and collide through the integrator of bevy_collision
+and terrain_collision_system. This system only puts voxels back into the grid once they came to rest.
+A voxel is on the ground if there is terrain right below its collider.
+ */
+pub fn settle_floating_voxels(
+    mut commands: Commands,
+    settings: Res<FloatingVoxelSettings>,
+    chunk_access: Res<VoxelAccess>,
+    time: Res<Time>,
+    mut update_events: ResMut<Events<WorldUpdateEvent>>,
+    mut voxel_query: Query<(Entity, &mut FreeFloatingVoxel, &Transform, &RigidBody)>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut voxel, transform, body) in voxel_query.iter_mut() {
+        if transform.translation.y < settings.despawn_below {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let below = transform.translation
+            - Vec3::new(0.0, FLOATING_VOXEL_COLLIDER_SIZE / 2.0 + GROUND_PROBE, 0.0);
+        let grounded = chunk_access
+            .get_voxel(VoxelPosition::from_vec3(&below))
+            .is_some();
+        if grounded && body.velocity.length() < REST_SPEED {
+            voxel.resting += delta;
+        } else {
+            voxel.resting = 0.0;
+        }
+
+        if let Some(settle_after) = settings.settle_after {
+            if voxel.resting >= settle_after {
+                update_events.send(settle(
+                    VoxelPosition::from_vec3(&transform.translation),
+                    voxel.typ,
+                ));
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+/// puts the voxel into the first empty position at or above position, it is dropped if there is none
+/// and falls again if the position is not connected to the ground
+fn settle(position: VoxelPosition, typ: VoxelTypes) -> WorldUpdateEvent {
+    WorldUpdateEvent::setting(move |access| {
+        (0..=MAX_SETTLE_LIFT)
+            .map(|lift| VoxelPosition::new(position.x, position.y + lift, position.z))
+            .find(|p| access.get_voxel(*p).is_none())
+            .map(|position| vec![Voxel { position, typ }])
+            .unwrap_or_default()
+    })
+    .detaching_floating()
+}
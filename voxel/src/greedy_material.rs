@@ -0,0 +1,147 @@
+use bevy::{
+    ecs::system::EntityCommands,
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        pipeline::{PipelineDescriptor, RenderPipeline},
+        render_graph::{base, AssetRenderResourcesNode, RenderGraph},
+        renderer::RenderResources,
+        shader::{ShaderStage, ShaderStages},
+    },
+};
+
+use crate::chunk_mesh::ChunkMesher;
+
+/// Material of greedy chunk meshes, see greedy_mesh::ATTRIBUTE_VOXEL_TILE
+#[derive(RenderResources, Default, TypeUuid)]
+#[uuid = "5f0b6a4e-8d7c-4f5e-9a41-2c3d7e6b1f08"]
+pub struct GreedyVoxelMaterial {
+    pub texture: Handle<Texture>,
+}
+
+pub struct VoxelTexture {
+    pub material: Handle<StandardMaterial>,
+    pub greedy_material: Handle<GreedyVoxelMaterial>,
+    pub greedy_pipeline: Handle<PipelineDescriptor>,
+}
+
+impl VoxelTexture {
+    pub fn new(
+        material: Handle<StandardMaterial>,
+        texture: Handle<Texture>,
+        pipelines: &mut Assets<PipelineDescriptor>,
+        shaders: &mut Assets<Shader>,
+        greedy_materials: &mut Assets<GreedyVoxelMaterial>,
+        render_graph: &mut RenderGraph,
+    ) -> VoxelTexture {
+        let greedy_pipeline = pipelines.add(PipelineDescriptor::default_config(ShaderStages {
+            vertex: shaders.add(Shader::from_glsl(ShaderStage::Vertex, GREEDY_VERTEX_SHADER)),
+            fragment: Some(shaders.add(Shader::from_glsl(
+                ShaderStage::Fragment,
+                GREEDY_FRAGMENT_SHADER,
+            ))),
+        }));
+
+        render_graph.add_system_node(
+            "greedy_voxel_material",
+            AssetRenderResourcesNode::<GreedyVoxelMaterial>::new(true),
+        );
+
+        render_graph
+            .add_node_edge("greedy_voxel_material", base::node::MAIN_PASS)
+            .unwrap();
+
+        VoxelTexture {
+            material,
+            greedy_material: greedy_materials.add(GreedyVoxelMaterial { texture }),
+            greedy_pipeline,
+        }
+    }
+
+    /// Face meshes use the pbr material, greedy meshes need the shader that wraps their uvs.
+    pub fn insert_chunk_mesh(
+        &self,
+        entity: &mut EntityCommands,
+        mesher: &ChunkMesher,
+        mesh: Handle<Mesh>,
+    ) {
+        match mesher {
+            ChunkMesher::Faces => {
+                entity.insert_bundle(PbrBundle {
+                    mesh,
+                    material: self.material.clone(),
+                    ..Default::default()
+                });
+            }
+            ChunkMesher::Greedy => {
+                entity
+                    .insert_bundle(MeshBundle {
+                        mesh,
+                        render_pipelines: RenderPipelines::from_pipelines(vec![
+                            RenderPipeline::new(self.greedy_pipeline.clone()),
+                        ]),
+                        ..Default::default()
+                    })
+                    .insert(self.greedy_material.clone());
+            }
+        }
+    }
+
+    pub fn remove_chunk_mesh(&self, entity: &mut EntityCommands, mesher: &ChunkMesher) {
+        match mesher {
+            ChunkMesher::Faces => {
+                entity.remove_bundle::<PbrBundle>();
+            }
+            ChunkMesher::Greedy => {
+                entity
+                    .remove_bundle::<MeshBundle>()
+                    .remove::<Handle<GreedyVoxelMaterial>>();
+            }
+        }
+    }
+}
+
+const GREEDY_VERTEX_SHADER: &str = r#"
+#version 450
+layout(location = 0) in vec3 Vertex_Position;
+layout(location = 1) in vec3 Vertex_Normal;
+layout(location = 2) in vec2 Vertex_Uv;
+layout(location = 3) in vec4 Voxel_Tile;
+layout(location = 0) out vec3 v_Normal;
+layout(location = 1) out vec2 v_Uv;
+layout(location = 2) out vec4 v_Tile;
+layout(set = 0, binding = 0) uniform CameraViewProj {
+    mat4 ViewProj;
+};
+layout(set = 1, binding = 0) uniform Transform {
+    mat4 Model;
+};
+void main() {
+    v_Normal = mat3(Model) * Vertex_Normal;
+    v_Uv = Vertex_Uv;
+    v_Tile = Voxel_Tile;
+    gl_Position = ViewProj * Model * vec4(Vertex_Position, 1.0);
+}
+"#;
+
+const GREEDY_FRAGMENT_SHADER: &str = r#"
+#version 450
+layout(location = 0) in vec3 v_Normal;
+layout(location = 1) in vec2 v_Uv;
+layout(location = 2) in vec4 v_Tile;
+layout(location = 0) out vec4 o_Target;
+layout(set = 2, binding = 0) uniform texture2D GreedyVoxelMaterial_texture;
+layout(set = 2, binding = 1) uniform sampler GreedyVoxelMaterial_texture_sampler;
+const vec3 SUN_DIRECTION = vec3(0.27, 0.92, 0.27);
+void main() {
+    // the uvs count voxels, every voxel of a merged quad repeats the tile
+    vec2 in_tile = fract(v_Uv);
+    vec2 uv = vec2(mix(v_Tile.x, v_Tile.y, in_tile.x), mix(v_Tile.z, v_Tile.w, in_tile.y));
+    vec4 color = texture(
+        sampler2D(GreedyVoxelMaterial_texture, GreedyVoxelMaterial_texture_sampler),
+        uv
+    );
+    float light = 0.3 + 0.7 * max(dot(normalize(v_Normal), SUN_DIRECTION), 0.0);
+    o_Target = vec4(color.rgb * light, color.a);
+}
+"#;
@@ -0,0 +1,352 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, pipeline::PrimitiveTopology},
+};
+use std::borrow::Cow;
+
+use crate::{
+    boundaries::CHUNK_SIZE,
+    chunk::VoxelChunk,
+    chunk_mesh::uvs_from_typ,
+    neighbours::ChunkNeighbours,
+    voxel::{VoxelPosition, VoxelTypes, HALF_VOXEL_SIZE, VOXEL_SIZE},
+};
+
+/// Atlas tile of each vertex as (u_min, u_max, v_min, v_max).
+/// The uvs of greedy quads count voxels, the shader of greedy_material::GreedyVoxelMaterial wraps them into this tile.
+pub const ATTRIBUTE_VOXEL_TILE: &str = "Voxel_Tile";
+
+/*
+Merges coplanar faces of the same type into larger quads.
+Each lod cell is treated as one voxel with the most common type of the voxels within the cell.
+For every axis and both directions the visible faces of a slice are collected into a mask,
+which is then greedily split into rectangles.
+ */
+pub fn greedy_mesh(chunk: &VoxelChunk, neighbours: &ChunkNeighbours) -> Mesh {
+    let mut builder = QuadBuilder::default();
+    if chunk.count > 0 {
+        let cells = Cells::from_chunk(chunk);
+        for axis in 0..3 {
+            for positive in [false, true].iter() {
+                for slice in 0..cells.size {
+                    let mask = cells.face_mask(chunk, neighbours, axis, *positive, slice);
+                    merge_mask(mask, cells.size, |a, b, width, height, typ| {
+                        builder.quad(
+                            chunk, &cells, axis, *positive, slice, a, b, width, height, typ,
+                        )
+                    });
+                }
+            }
+        }
+    }
+    builder.build()
+}
+
+struct Cells {
+    size: i32,
+    lod: i32,
+    types: Vec<Option<VoxelTypes>>,
+}
+
+impl Cells {
+    fn from_chunk(chunk: &VoxelChunk) -> Cells {
+        let lod = chunk.lod.clamp(1, CHUNK_SIZE);
+        let size = CHUNK_SIZE / lod;
+        let mut types = Vec::with_capacity((size * size * size) as usize);
+        for z in 0..size {
+            for y in 0..size {
+                for x in 0..size {
+                    types.push(Cells::cell_type(chunk, lod, [x, y, z]));
+                }
+            }
+        }
+        Cells { size, lod, types }
+    }
+
+    fn cell_type(chunk: &VoxelChunk, lod: i32, cell: [i32; 3]) -> Option<VoxelTypes> {
+        if lod == 1 {
+            return chunk.get(&VoxelPosition {
+                x: chunk.boundary.min[0] + cell[0],
+                y: chunk.boundary.min[1] + cell[1],
+                z: chunk.boundary.min[2] + cell[2],
+            });
+        }
+        let mut type_2_count = [0u32; 8];
+        for z in 0..lod {
+            for y in 0..lod {
+                for x in 0..lod {
+                    let position = VoxelPosition {
+                        x: chunk.boundary.min[0] + cell[0] * lod + x,
+                        y: chunk.boundary.min[1] + cell[1] * lod + y,
+                        z: chunk.boundary.min[2] + cell[2] * lod + z,
+                    };
+                    if let Some(typ) = chunk.get(&position) {
+                        type_2_count[typ.to_u8() as usize] += 1;
+                    }
+                }
+            }
+        }
+        type_2_count
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c > 0)
+            .max_by_key(|(_, c)| **c)
+            .and_then(|(t, _)| VoxelTypes::from_u8(t as u8))
+    }
+
+    fn get(&self, cell: [i32; 3]) -> Option<VoxelTypes> {
+        if self.is_inside(cell) {
+            self.types[(cell[2] * self.size * self.size + cell[1] * self.size + cell[0]) as usize]
+        } else {
+            None
+        }
+    }
+
+    fn is_inside(&self, cell: [i32; 3]) -> bool {
+        cell.iter().all(|c| *c >= 0 && *c < self.size)
+    }
+
+    /// a cell outside of the chunk only covers the face if every touching voxel of the neighbouring chunk is solid,
+    /// since the neighbour may be rendered with a different lod
+    fn is_neighbour_covering(
+        &self,
+        chunk: &VoxelChunk,
+        neighbours: &ChunkNeighbours,
+        axis: usize,
+        cell: [i32; 3],
+    ) -> bool {
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let mut p = [0; 3];
+        p[axis] = if cell[axis] < 0 {
+            chunk.boundary.min[axis] - 1
+        } else {
+            chunk.boundary.max[axis]
+        };
+        for b in 0..self.lod {
+            for a in 0..self.lod {
+                p[u] = chunk.boundary.min[u] + cell[u] * self.lod + a;
+                p[v] = chunk.boundary.min[v] + cell[v] * self.lod + b;
+                if !neighbours.is_solid(&VoxelPosition {
+                    x: p[0],
+                    y: p[1],
+                    z: p[2],
+                }) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// types of the faces of the slice that point towards the given direction of the axis
+    fn face_mask(
+        &self,
+        chunk: &VoxelChunk,
+        neighbours: &ChunkNeighbours,
+        axis: usize,
+        positive: bool,
+        slice: i32,
+    ) -> Vec<Option<VoxelTypes>> {
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let mut mask = Vec::with_capacity((self.size * self.size) as usize);
+        for b in 0..self.size {
+            for a in 0..self.size {
+                let mut cell = [0; 3];
+                cell[axis] = slice;
+                cell[u] = a;
+                cell[v] = b;
+                let mut neighbour = cell;
+                neighbour[axis] += if positive { 1 } else { -1 };
+                let covered = if self.is_inside(neighbour) {
+                    self.get(neighbour).is_some()
+                } else {
+                    self.is_neighbour_covering(chunk, neighbours, axis, neighbour)
+                };
+                mask.push(if covered { None } else { self.get(cell) });
+            }
+        }
+        mask
+    }
+}
+
+fn merge_mask<F>(mut mask: Vec<Option<VoxelTypes>>, size: i32, mut emit: F)
+where
+    F: FnMut(i32, i32, i32, i32, VoxelTypes),
+{
+    let index = |a: i32, b: i32| (b * size + a) as usize;
+    for b in 0..size {
+        let mut a = 0;
+        while a < size {
+            if let Some(typ) = mask[index(a, b)] {
+                let mut width = 1;
+                while a + width < size && mask[index(a + width, b)] == Some(typ) {
+                    width += 1;
+                }
+                let mut height = 1;
+                'grow: while b + height < size {
+                    for k in a..a + width {
+                        if mask[index(k, b + height)] != Some(typ) {
+                            break 'grow;
+                        }
+                    }
+                    height += 1;
+                }
+                for h in b..b + height {
+                    for k in a..a + width {
+                        mask[index(k, h)] = None;
+                    }
+                }
+                emit(a, b, width, height, typ);
+                a += width;
+            } else {
+                a += 1;
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct QuadBuilder {
+    vertices: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    tangents: Vec<[f32; 4]>,
+    uvs: Vec<[f32; 2]>,
+    tiles: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+}
+
+impl QuadBuilder {
+    #[allow(clippy::too_many_arguments)]
+    fn quad(
+        &mut self,
+        chunk: &VoxelChunk,
+        cells: &Cells,
+        axis: usize,
+        positive: bool,
+        slice: i32,
+        a: i32,
+        b: i32,
+        width: i32,
+        height: i32,
+        typ: VoxelTypes,
+    ) {
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let cell_2_world = |dimension: usize, cell: i32| {
+            (chunk.boundary.min[dimension] + cell * cells.lod) as f32 * VOXEL_SIZE - HALF_VOXEL_SIZE
+        };
+        let plane = cell_2_world(axis, if positive { slice + 1 } else { slice });
+        let corner = |a: i32, b: i32| {
+            let mut p = [0.0f32; 3];
+            p[axis] = plane;
+            p[u] = cell_2_world(u, a);
+            p[v] = cell_2_world(v, b);
+            p
+        };
+
+        let mut normal = [0.0f32; 3];
+        normal[axis] = if positive { 1.0 } else { -1.0 };
+        let mut tangent = [0.0f32, 0.0, 0.0, 1.0];
+        tangent[u] = 1.0;
+
+        let start = self.vertices.len() as u32;
+        self.vertices.push(corner(a, b));
+        self.vertices.push(corner(a + width, b));
+        self.vertices.push(corner(a + width, b + height));
+        self.vertices.push(corner(a, b + height));
+
+        let (u_min, u_max, v_min, v_max) = uvs_from_typ(&typ);
+        let voxels_u = (width * cells.lod) as f32;
+        let voxels_v = (height * cells.lod) as f32;
+        self.uvs.push([0.0, 0.0]);
+        self.uvs.push([voxels_u, 0.0]);
+        self.uvs.push([voxels_u, voxels_v]);
+        self.uvs.push([0.0, voxels_v]);
+        for _ in 0..4 {
+            self.normals.push(normal);
+            self.tangents.push(tangent);
+            self.tiles.push([u_min, u_max, v_min, v_max]);
+        }
+
+        if positive {
+            self.indices.extend_from_slice(&[
+                start,
+                start + 1,
+                start + 2,
+                start,
+                start + 2,
+                start + 3,
+            ]);
+        } else {
+            self.indices.extend_from_slice(&[
+                start,
+                start + 2,
+                start + 1,
+                start,
+                start + 3,
+                start + 2,
+            ]);
+        }
+    }
+
+    fn build(self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_attribute(Cow::Borrowed(Mesh::ATTRIBUTE_POSITION), self.vertices);
+        mesh.set_attribute(Cow::Borrowed(Mesh::ATTRIBUTE_NORMAL), self.normals);
+        mesh.set_attribute(Cow::Borrowed(Mesh::ATTRIBUTE_TANGENT), self.tangents);
+        mesh.set_attribute(Cow::Borrowed(Mesh::ATTRIBUTE_UV_0), self.uvs);
+        mesh.set_attribute(ATTRIBUTE_VOXEL_TILE, self.tiles);
+        mesh.set_indices(Some(Indices::U32(self.indices)));
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::iproduct;
+
+    use crate::{
+        boundaries::{ChunkBoundaries, CHUNK_SIZE},
+        chunk::VoxelChunk,
+        chunk_mesh::ChunkMesher,
+        neighbours::ChunkNeighbours,
+        voxel::{Voxel, VoxelPosition, VoxelTypes},
+    };
+
+    #[test]
+    fn full_chunk_is_one_quad_per_side() {
+        let boundaries = ChunkBoundaries::aligned(VoxelPosition::new(0, 0, 0));
+        let mut chunk = VoxelChunk::empty(boundaries);
+        for (x, y, z) in iproduct!(0..CHUNK_SIZE, 0..CHUNK_SIZE, 0..CHUNK_SIZE) {
+            chunk.set(Voxel::new(x, y, z, VoxelTypes::GreyRock1));
+        }
+
+        assert_eq!(
+            ChunkMesher::Greedy
+                .mesh(&chunk, &ChunkNeighbours::none(boundaries))
+                .count_vertices(),
+            6 * 4
+        );
+    }
+
+    #[test]
+    fn greedy_mesh_has_fewer_vertices_for_flat_terrain() {
+        let boundaries = ChunkBoundaries::aligned(VoxelPosition::new(0, 0, 0));
+        let mut chunk = VoxelChunk::empty(boundaries);
+        for (x, y, z) in iproduct!(0..CHUNK_SIZE, 0..10, 0..CHUNK_SIZE) {
+            let typ = if y == 9 {
+                VoxelTypes::Moss
+            } else {
+                VoxelTypes::BrownRock
+            };
+            chunk.set(Voxel::new(x, y, z, typ));
+        }
+
+        let faces = ChunkMesher::Faces
+            .mesh(&chunk, &ChunkNeighbours::none(boundaries))
+            .count_vertices();
+        let greedy = ChunkMesher::Greedy
+            .mesh(&chunk, &ChunkNeighbours::none(boundaries))
+            .count_vertices();
+        assert!(greedy * 100 < faces);
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::VecDeque;
+
+use ahash::AHashSet;
+use strum::IntoEnumIterator;
+
+use crate::{
+    access::VoxelAccess,
+    voxel::{VoxelDirection, VoxelPosition},
+};
+
+/// connected voxel groups of at least this size are considered part of the ground
+pub const MAX_ISLAND_SIZE: usize = 2048;
+
+/*
+Finds the groups of voxels next to the removed positions that are no longer connected to the ground.
+The ground is not known explicitly, instead a flood fill from every solid neighbour of a removed position stops
+as soon as the group grows beyond max_size or reaches a chunk that is not loaded.
+Groups that are found completely are floating.
+Every voxel reached by an anchored flood fill is anchored, so later starts touching it are skipped.
+ */
+pub fn floating_islands(
+    access: &VoxelAccess,
+    removed: &[VoxelPosition],
+    max_size: usize,
+) -> Vec<Vec<VoxelPosition>> {
+    let neighbours = removed
+        .iter()
+        .flat_map(|position| VoxelDirection::iter().map(move |d| position.in_direction(d)));
+    islands_from(access, neighbours, max_size)
+}
+
+/// the groups of the placed voxels that are not connected to the ground, see floating_islands
+pub fn floating_placed(
+    access: &VoxelAccess,
+    placed: &[VoxelPosition],
+    max_size: usize,
+) -> Vec<Vec<VoxelPosition>> {
+    islands_from(access, placed.iter().cloned(), max_size)
+}
+
+fn islands_from(
+    access: &VoxelAccess,
+    starts: impl Iterator<Item = VoxelPosition>,
+    max_size: usize,
+) -> Vec<Vec<VoxelPosition>> {
+    let mut checked = AHashSet::new();
+    // voxels connected to an anchored group, every start reaching them is anchored as well
+    let mut anchored = AHashSet::new();
+    let mut islands = Vec::new();
+    for start in starts {
+        if checked.contains(&start)
+            || anchored.contains(&start)
+            || access.get_voxel(start).is_none()
+        {
+            continue;
+        }
+        match flood_fill(access, start, max_size) {
+            Ok(island) => {
+                checked.extend(island.iter().cloned());
+                islands.push(island);
+            }
+            Err(visited) => anchored.extend(visited),
+        }
+    }
+    islands
+}
+
+/// Err with the visited voxels if the group is anchored
+fn flood_fill(
+    access: &VoxelAccess,
+    start: VoxelPosition,
+    max_size: usize,
+) -> Result<Vec<VoxelPosition>, AHashSet<VoxelPosition>> {
+    let mut visited = AHashSet::new();
+    let mut queue = VecDeque::new();
+    visited.insert(start);
+    queue.push_back(start);
+    while let Some(position) = queue.pop_front() {
+        for direction in VoxelDirection::iter() {
+            let neighbour = position.in_direction(direction);
+            if visited.contains(&neighbour) {
+                continue;
+            }
+            let chunk = match access.get_chunk_containing(neighbour) {
+                Some(chunk) => chunk,
+                None => return Err(visited),
+            };
+            if chunk.get(&neighbour).is_some() {
+                if visited.len() >= max_size {
+                    return Err(visited);
+                }
+                visited.insert(neighbour);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+    Ok(visited.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Entity;
+
+    use crate::{
+        access::VoxelAccess,
+        boundaries::ChunkBoundaries,
+        chunk::VoxelChunk,
+        voxel::{Voxel, VoxelPosition, VoxelTypes},
+    };
+
+    use super::{floating_islands, floating_placed};
+
+    /// a column from y 0 to 19 standing on a slab, everything within one chunk
+    fn pillar() -> VoxelAccess {
+        let boundary = ChunkBoundaries::aligned(VoxelPosition::new(0, 0, 0));
+        let mut chunk = VoxelChunk::empty(boundary);
+        for x in 0..10 {
+            for z in 0..10 {
+                chunk.set(Voxel::new(x, 0, z, VoxelTypes::GreyRock1));
+            }
+        }
+        for y in 1..20 {
+            chunk.set(Voxel::new(5, y, 5, VoxelTypes::GreyRock1));
+        }
+        let mut access = VoxelAccess::new();
+        access.add_chunk(boundary, Entity::new(0), chunk);
+        access
+    }
+
+    #[test]
+    fn cut_off_voxels_are_floating() {
+        let mut access = pillar();
+        let cut = VoxelPosition::new(5, 10, 5);
+        access.get_chunk_containing_mut(cut).unwrap().remove(cut);
+
+        let islands = floating_islands(&access, &[cut], 50);
+
+        assert_eq!(islands.len(), 1);
+        assert_eq!(islands[0].len(), 9);
+        assert!(islands[0].iter().all(|p| p.y > 10));
+    }
+
+    #[test]
+    fn groups_larger_than_the_limit_are_anchored() {
+        let mut access = pillar();
+        let cut = VoxelPosition::new(5, 10, 5);
+        access.get_chunk_containing_mut(cut).unwrap().remove(cut);
+
+        assert!(floating_islands(&access, &[cut], 5).is_empty());
+    }
+
+    #[test]
+    fn starts_connected_to_an_anchored_group_are_skipped() {
+        let mut access = pillar();
+        let cuts = [
+            VoxelPosition::new(2, 0, 2),
+            VoxelPosition::new(3, 0, 2),
+            VoxelPosition::new(5, 10, 5),
+        ];
+        for cut in cuts.iter() {
+            access.get_chunk_containing_mut(*cut).unwrap().remove(*cut);
+        }
+
+        let islands = floating_islands(&access, &cuts, 50);
+
+        assert_eq!(islands.len(), 1);
+        assert!(islands[0].iter().all(|p| p.y > 10));
+    }
+
+    #[test]
+    fn groups_reaching_unloaded_chunks_are_anchored() {
+        let mut access = pillar();
+        let top = VoxelPosition::new(5, 63, 5);
+        for y in 20..64 {
+            access
+                .get_chunk_containing_mut(top)
+                .unwrap()
+                .set(Voxel::new(5, y, 5, VoxelTypes::GreyRock1));
+        }
+        let cut = VoxelPosition::new(5, 10, 5);
+        access.get_chunk_containing_mut(cut).unwrap().remove(cut);
+
+        let islands = floating_islands(&access, &[cut], 1000);
+
+        assert!(islands.is_empty());
+    }
+
+    #[test]
+    fn placed_voxels_without_support_are_floating() {
+        let mut access = pillar();
+        let supported = VoxelPosition::new(5, 20, 5);
+        let unsupported = VoxelPosition::new(5, 22, 5);
+        for position in [supported, unsupported].iter() {
+            access
+                .get_chunk_containing_mut(*position)
+                .unwrap()
+                .set(Voxel::new(
+                    position.x,
+                    position.y,
+                    position.z,
+                    VoxelTypes::GreyRock1,
+                ));
+        }
+
+        let islands = floating_placed(&access, &[supported, unsupported], 50);
+
+        assert_eq!(islands, vec![vec![unsupported]]);
+    }
+}
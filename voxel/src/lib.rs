@@ -3,47 +3,69 @@ pub mod boundaries;
 pub mod chunk;
 pub mod chunk_mesh;
 pub mod collision;
+pub mod edit_log;
 mod effects;
 mod evaluation;
-pub mod generator;
+pub mod falling;
+pub mod greedy_material;
+pub mod greedy_mesh;
+pub mod integrity;
 mod lod;
 mod mesh;
 pub mod model;
+pub mod neighbours;
+pub mod persistence;
+pub mod shapes;
+pub mod storage;
 pub mod voxel;
 pub mod water;
-mod world_gen;
+pub mod world_gen;
 
-use ahash::AHashMap;
 use bevy::prelude::Plugin;
 use bevy::prelude::*;
-use bevy_collision::collider::{Collider, ColliderShapes};
-use boundaries::ChunkBoundaries;
+use bevy::render::{pipeline::PipelineDescriptor, render_graph::RenderGraph};
+use bevy_collision::{
+    collider::{Collider, ColliderShapes},
+    layers::CollisionFilter,
+};
+use chunk_mesh::ChunkMesher;
 
 use flume::unbounded;
-use generator::VoxelWorld;
-use rand::prelude::*;
-
-use crate::voxel::Voxel;
 
+pub use crate::greedy_material::VoxelTexture;
 use crate::{
+    edit_log::EditLog,
     effects::{erosion, move_floating_voxels},
     evaluation::{
         evaluate_delayed_transformations, update_world_event_reader, update_world_from_channel,
     },
-    model::{DelayedWorldTransformations, WorldUpdateEvent, WorldUpdateResult},
-    world_gen::{read_generation_results, setup_world_gen, start_generation},
+    falling::{settle_floating_voxels, FloatingVoxelSettings},
+    greedy_material::GreedyVoxelMaterial,
+    model::{
+        ChunkRemeshRequests, DelayedWorldTransformations, WorldUpdateEvent, WorldUpdateResult,
+    },
+    persistence::{save_modified_chunks, setup_chunk_store, ChunkSaveTimer},
+    storage::{measure_voxel_memory, setup_voxel_memory_diagnostic},
+    voxel::VoxelTypes,
+    world_gen::{
+        read_generation_results, setup_world_gen, start_generation, unload_distant_chunks,
+        WorldSeed,
+    },
 };
 
-pub struct VoxelTexture {
-    pub material: Handle<StandardMaterial>,
+/// voxel that was detached from the terrain, see falling::settle_floating_voxels
+pub struct FreeFloatingVoxel {
+    pub typ: VoxelTypes,
+    /// seconds the voxel has been lying on the ground
+    pub resting: f32,
 }
 
-pub struct AdditionalVoxels {
-    voxels: AHashMap<ChunkBoundaries, Vec<Voxel>>,
+impl FreeFloatingVoxel {
+    pub fn new(typ: VoxelTypes) -> FreeFloatingVoxel {
+        FreeFloatingVoxel { typ, resting: 0.0 }
+    }
 }
 
-pub struct FreeFloatingVoxel;
-
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
@@ -54,16 +76,31 @@ impl Plugin for WorldPlugin {
             .insert_resource(DelayedWorldTransformations {
                 transformations: Vec::new(),
             })
+            .insert_resource(ChunkMesher::Faces)
+            .add_asset::<GreedyVoxelMaterial>()
+            .init_resource::<ChunkRemeshRequests>()
+            .init_resource::<EditLog>()
+            .init_resource::<FloatingVoxelSettings>()
+            .init_resource::<WorldSeed>()
+            .add_startup_system_to_stage(StartupStage::PreStartup, setup_chunk_store.system())
+            .insert_resource(ChunkSaveTimer {
+                timer: Timer::from_seconds(30.0, true),
+            })
             .add_event::<WorldUpdateEvent>()
             .add_system(update_world_from_channel.system())
             .add_system(update_world_event_reader.system())
             .add_system(erosion.system())
             .add_system(evaluate_delayed_transformations.system())
             .add_system(move_floating_voxels.system())
+            .add_system(settle_floating_voxels.system())
             .add_startup_system(world_setup.system())
             .add_startup_system(setup_world_gen.system())
             .add_system(start_generation.system())
-            .add_system(read_generation_results.system());
+            .add_system(read_generation_results.system())
+            .add_system(unload_distant_chunks.system())
+            .add_system_to_stage(CoreStage::Last, save_modified_chunks.system())
+            .add_startup_system(setup_voxel_memory_diagnostic.system())
+            .add_system(measure_voxel_memory.system());
     }
 }
 
@@ -71,6 +108,10 @@ fn world_setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut greedy_materials: ResMut<Assets<GreedyVoxelMaterial>>,
+    mut pipelines: ResMut<Assets<PipelineDescriptor>>,
+    mut shaders: ResMut<Assets<Shader>>,
+    mut render_graph: ResMut<RenderGraph>,
     asset_server: Res<AssetServer>,
 ) {
     let chunk_texture = asset_server.load("world_texture_color.png");
@@ -78,29 +119,21 @@ fn world_setup(
     let chunk_normal = asset_server.load("world_texture_normal.png");
 
     let chunk_material = materials.add(StandardMaterial {
-        base_color_texture: Some(chunk_texture),
+        base_color_texture: Some(chunk_texture.clone()),
         metallic_roughness_texture: Some(chunk_roughness),
         metallic: 0.2,
         roughness: 1.0,
         normal_map: Some(chunk_normal),
         ..Default::default()
     });
-    commands.insert_resource(VoxelTexture {
-        material: chunk_material,
-    });
-
-    let w = VoxelWorld::generate(150, 150, SmallRng::from_entropy());
-    let mut chunk_map = AHashMap::new();
-    for pillar in w.pillars {
-        for voxel in pillar.voxels() {
-            let matching_boundary = ChunkBoundaries::aligned(voxel.position);
-            chunk_map
-                .entry(matching_boundary)
-                .or_insert(vec![])
-                .push(voxel);
-        }
-    }
-    commands.insert_resource(AdditionalVoxels { voxels: chunk_map });
+    commands.insert_resource(VoxelTexture::new(
+        chunk_material,
+        chunk_texture,
+        &mut pipelines,
+        &mut shaders,
+        &mut greedy_materials,
+        &mut render_graph,
+    ));
 
     commands.spawn_bundle(PointLightBundle {
         transform: Transform::from_translation(Vec3::new(0.0, 100.0, 0.0)),
@@ -132,5 +165,7 @@ fn world_setup(
                 half_depth_z: 0.25,
             },
             local_position: Vec3::new(0.0, 0.0, 0.0),
+            filter: CollisionFilter::default(),
+            sensor: false,
         });
 }
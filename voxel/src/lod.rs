@@ -1,7 +1,7 @@
 use std::ops::AddAssign;
 
 use super::voxel::{VoxelDirection, VoxelPosition};
-use super::{chunk::VoxelChunk, voxel::VoxelFace};
+use super::{chunk::VoxelChunk, neighbours::ChunkNeighbours, voxel::VoxelFace};
 use crate::voxel::{VoxelTypes, HALF_VOXEL_SIZE};
 use bevy::prelude::Vec3;
 use itertools::iproduct;
@@ -24,7 +24,7 @@ pub fn distance_2_lod(distance: f32) -> i32 {
     }
 }
 
-pub fn combine_voxels(chunk: &VoxelChunk) -> Vec<VoxelFace> {
+pub fn combine_voxels(chunk: &VoxelChunk, neighbours: &ChunkNeighbours) -> Vec<VoxelFace> {
     if chunk.count == 0 {
         return Vec::new();
     }
@@ -40,31 +40,55 @@ pub fn combine_voxels(chunk: &VoxelChunk) -> Vec<VoxelFace> {
             .step_by(chunk.lod as usize)
             .into_iter()
     ) {
-        top_face(base_x, base_y, base_z, chunk).map(|f| faces.push(f));
-        bottom_face(base_x, base_y, base_z, chunk).map(|f| faces.push(f));
-        left_face(base_x, base_y, base_z, chunk).map(|f| faces.push(f));
-        right_face(base_x, base_y, base_z, chunk).map(|f| faces.push(f));
-        front_face(base_x, base_y, base_z, chunk).map(|f| faces.push(f));
-        back_face(base_x, base_y, base_z, chunk).map(|f| faces.push(f));
+        top_face(base_x, base_y, base_z, chunk, neighbours).map(|f| faces.push(f));
+        bottom_face(base_x, base_y, base_z, chunk, neighbours).map(|f| faces.push(f));
+        left_face(base_x, base_y, base_z, chunk, neighbours).map(|f| faces.push(f));
+        right_face(base_x, base_y, base_z, chunk, neighbours).map(|f| faces.push(f));
+        front_face(base_x, base_y, base_z, chunk, neighbours).map(|f| faces.push(f));
+        back_face(base_x, base_y, base_z, chunk, neighbours).map(|f| faces.push(f));
     }
     faces
 }
 
-fn top_face(base_x: i32, base_y: i32, base_z: i32, chunk: &VoxelChunk) -> Option<VoxelFace> {
-    if base_y + chunk.lod < chunk.boundary.max[1] {
-        for (x, z) in iproduct!(
-            (base_x..base_x + chunk.lod).into_iter(),
-            (base_z..base_z + chunk.lod).into_iter()
-        ) {
-            let position = VoxelPosition {
-                x,
-                z,
-                y: base_y + chunk.lod,
-            };
-            if chunk.get(&position).is_some() {
-                return None;
-            }
+/*
+Whether the voxels touching a face hide it.
+Inside the chunk a single solid voxel is enough, because the cell containing it is rendered as a whole.
+Across the chunk border the neighbour may be rendered with a different lod. Hiding the face only if every touching
+voxel is solid means the neighbour renders the whole face area, independent of its lod, so no holes appear
+between chunks of different lods.
+ */
+fn is_covered<I: Iterator<Item = VoxelPosition>>(
+    chunk: &VoxelChunk,
+    neighbours: &ChunkNeighbours,
+    mut touching: I,
+) -> bool {
+    match touching.next() {
+        Some(first) if chunk.boundary.contains(&first) => {
+            chunk.get(&first).is_some() || touching.any(|p| chunk.get(&p).is_some())
         }
+        Some(first) => neighbours.is_solid(&first) && touching.all(|p| neighbours.is_solid(&p)),
+        None => false,
+    }
+}
+
+fn top_face(
+    base_x: i32,
+    base_y: i32,
+    base_z: i32,
+    chunk: &VoxelChunk,
+    neighbours: &ChunkNeighbours,
+) -> Option<VoxelFace> {
+    let touching = iproduct!(
+        (base_x..base_x + chunk.lod).into_iter(),
+        (base_z..base_z + chunk.lod).into_iter()
+    )
+    .map(|(x, z)| VoxelPosition {
+        x,
+        z,
+        y: base_y + chunk.lod,
+    });
+    if is_covered(chunk, neighbours, touching) {
+        return None;
     }
 
     let mut type_2_count: [(VoxelTypes, i32); 8] = [
@@ -113,21 +137,24 @@ fn top_face(base_x: i32, base_y: i32, base_z: i32, chunk: &VoxelChunk) -> Option
         })
 }
 
-fn bottom_face(base_x: i32, base_y: i32, base_z: i32, chunk: &VoxelChunk) -> Option<VoxelFace> {
-    if base_y - 1 >= chunk.boundary.min[1] {
-        for (x, z) in iproduct!(
-            (base_x..base_x + chunk.lod).into_iter(),
-            (base_z..base_z + chunk.lod).into_iter()
-        ) {
-            let position = VoxelPosition {
-                x,
-                z,
-                y: base_y - 1,
-            };
-            if chunk.get(&position).is_some() {
-                return None;
-            }
-        }
+fn bottom_face(
+    base_x: i32,
+    base_y: i32,
+    base_z: i32,
+    chunk: &VoxelChunk,
+    neighbours: &ChunkNeighbours,
+) -> Option<VoxelFace> {
+    let touching = iproduct!(
+        (base_x..base_x + chunk.lod).into_iter(),
+        (base_z..base_z + chunk.lod).into_iter()
+    )
+    .map(|(x, z)| VoxelPosition {
+        x,
+        z,
+        y: base_y - 1,
+    });
+    if is_covered(chunk, neighbours, touching) {
+        return None;
     }
 
     let mut type_2_count: [(VoxelTypes, i32); 8] = [
@@ -176,21 +203,24 @@ fn bottom_face(base_x: i32, base_y: i32, base_z: i32, chunk: &VoxelChunk) -> Opt
         })
 }
 
-fn left_face(base_x: i32, base_y: i32, base_z: i32, chunk: &VoxelChunk) -> Option<VoxelFace> {
-    if base_x - 1 >= chunk.boundary.min[0] {
-        for (y, z) in iproduct!(
-            (base_y..base_y + chunk.lod).into_iter(),
-            (base_z..base_z + chunk.lod).into_iter()
-        ) {
-            let position = VoxelPosition {
-                z,
-                y,
-                x: base_x - 1,
-            };
-            if chunk.get(&position).is_some() {
-                return None;
-            }
-        }
+fn left_face(
+    base_x: i32,
+    base_y: i32,
+    base_z: i32,
+    chunk: &VoxelChunk,
+    neighbours: &ChunkNeighbours,
+) -> Option<VoxelFace> {
+    let touching = iproduct!(
+        (base_y..base_y + chunk.lod).into_iter(),
+        (base_z..base_z + chunk.lod).into_iter()
+    )
+    .map(|(y, z)| VoxelPosition {
+        z,
+        y,
+        x: base_x - 1,
+    });
+    if is_covered(chunk, neighbours, touching) {
+        return None;
     }
 
     let mut type_2_count: [(VoxelTypes, i32); 8] = [
@@ -239,21 +269,24 @@ fn left_face(base_x: i32, base_y: i32, base_z: i32, chunk: &VoxelChunk) -> Optio
         })
 }
 
-fn right_face(base_x: i32, base_y: i32, base_z: i32, chunk: &VoxelChunk) -> Option<VoxelFace> {
-    if base_x + chunk.lod < chunk.boundary.max[0] {
-        for (y, z) in iproduct!(
-            (base_y..base_y + chunk.lod).into_iter(),
-            (base_z..base_z + chunk.lod).into_iter()
-        ) {
-            let position = VoxelPosition {
-                z,
-                y,
-                x: base_x + chunk.lod,
-            };
-            if chunk.get(&position).is_some() {
-                return None;
-            }
-        }
+fn right_face(
+    base_x: i32,
+    base_y: i32,
+    base_z: i32,
+    chunk: &VoxelChunk,
+    neighbours: &ChunkNeighbours,
+) -> Option<VoxelFace> {
+    let touching = iproduct!(
+        (base_y..base_y + chunk.lod).into_iter(),
+        (base_z..base_z + chunk.lod).into_iter()
+    )
+    .map(|(y, z)| VoxelPosition {
+        z,
+        y,
+        x: base_x + chunk.lod,
+    });
+    if is_covered(chunk, neighbours, touching) {
+        return None;
     }
 
     let mut type_2_count: [(VoxelTypes, i32); 8] = [
@@ -302,21 +335,24 @@ fn right_face(base_x: i32, base_y: i32, base_z: i32, chunk: &VoxelChunk) -> Opti
         })
 }
 
-fn front_face(base_x: i32, base_y: i32, base_z: i32, chunk: &VoxelChunk) -> Option<VoxelFace> {
-    if base_z - 1 >= chunk.boundary.min[2] {
-        for (x, y) in iproduct!(
-            (base_x..base_x + chunk.lod).into_iter(),
-            (base_y..base_y + chunk.lod).into_iter()
-        ) {
-            let position = VoxelPosition {
-                x,
-                y,
-                z: base_z - 1,
-            };
-            if chunk.get(&position).is_some() {
-                return None;
-            }
-        }
+fn front_face(
+    base_x: i32,
+    base_y: i32,
+    base_z: i32,
+    chunk: &VoxelChunk,
+    neighbours: &ChunkNeighbours,
+) -> Option<VoxelFace> {
+    let touching = iproduct!(
+        (base_x..base_x + chunk.lod).into_iter(),
+        (base_y..base_y + chunk.lod).into_iter()
+    )
+    .map(|(x, y)| VoxelPosition {
+        x,
+        y,
+        z: base_z - 1,
+    });
+    if is_covered(chunk, neighbours, touching) {
+        return None;
     }
 
     let mut type_2_count: [(VoxelTypes, i32); 8] = [
@@ -365,21 +401,24 @@ fn front_face(base_x: i32, base_y: i32, base_z: i32, chunk: &VoxelChunk) -> Opti
         })
 }
 
-fn back_face(base_x: i32, base_y: i32, base_z: i32, chunk: &VoxelChunk) -> Option<VoxelFace> {
-    if base_z + chunk.lod < chunk.boundary.max[2] {
-        for (x, y) in iproduct!(
-            (base_x..base_x + chunk.lod).into_iter(),
-            (base_y..base_y + chunk.lod).into_iter()
-        ) {
-            let position = VoxelPosition {
-                x,
-                y,
-                z: base_z + chunk.lod,
-            };
-            if chunk.get(&position).is_some() {
-                return None;
-            }
-        }
+fn back_face(
+    base_x: i32,
+    base_y: i32,
+    base_z: i32,
+    chunk: &VoxelChunk,
+    neighbours: &ChunkNeighbours,
+) -> Option<VoxelFace> {
+    let touching = iproduct!(
+        (base_x..base_x + chunk.lod).into_iter(),
+        (base_y..base_y + chunk.lod).into_iter()
+    )
+    .map(|(x, y)| VoxelPosition {
+        x,
+        y,
+        z: base_z + chunk.lod,
+    });
+    if is_covered(chunk, neighbours, touching) {
+        return None;
     }
 
     let mut type_2_count: [(VoxelTypes, i32); 8] = [
@@ -443,9 +482,157 @@ fn construct_face(
     }
     .to_vec();
 
-    if lod != 1 {
-        center += Vec3::ONE * lod as f32 * HALF_VOXEL_SIZE;
-    }
+    // the base voxel is centered on its position, so the cell extends lod - 1 voxels beyond it
+    center += Vec3::ONE * (lod - 1) as f32 * HALF_VOXEL_SIZE;
 
     VoxelFace::from_voxels(center, typ, direction, lod)
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        prelude::{Entity, Mesh},
+        render::mesh::VertexAttributeValues,
+    };
+    use itertools::iproduct;
+
+    use crate::{
+        access::VoxelAccess,
+        boundaries::{ChunkBoundaries, CHUNK_SIZE},
+        chunk::VoxelChunk,
+        chunk_mesh::ChunkMesher,
+        neighbours::ChunkNeighbours,
+        voxel::{Voxel, VoxelPosition, VoxelTypes, HALF_VOXEL_SIZE},
+    };
+
+    fn terrain_chunk(boundary: ChunkBoundaries, lod: i32) -> VoxelChunk {
+        let mut chunk = VoxelChunk::empty(boundary);
+        for (x, z) in iproduct!(
+            boundary.min[0]..boundary.max[0],
+            boundary.min[2]..boundary.max[2]
+        ) {
+            let height = 8 + (x * 7 + z * 13).rem_euclid(11);
+            for y in boundary.min[1]..boundary.min[1] + height {
+                chunk.set(Voxel::new(x, y, z, VoxelTypes::BrownRock));
+            }
+        }
+        chunk.lod = lod;
+        chunk
+    }
+
+    /// whether the lod cell containing the position is rendered as solid
+    fn is_rendered_solid(chunk: &VoxelChunk, position: [i32; 3]) -> bool {
+        let mut base = [0; 3];
+        for i in 0..3 {
+            base[i] = chunk.boundary.min[i]
+                + (position[i] - chunk.boundary.min[i]) / chunk.lod * chunk.lod;
+        }
+        iproduct!(0..chunk.lod, 0..chunk.lod, 0..chunk.lod).any(|(x, y, z)| {
+            chunk
+                .get(&VoxelPosition::new(base[0] + x, base[1] + y, base[2] + z))
+                .is_some()
+        })
+    }
+
+    /// quads of the mesh facing +x (true) or -x (false) as (x, y_min, y_max, z_min, z_max)
+    fn x_quads(mesh: &Mesh, positive: bool) -> Vec<(f32, f32, f32, f32, f32)> {
+        let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap() {
+            VertexAttributeValues::Float32x3(positions) => positions,
+            _ => panic!("positions in wrong format"),
+        };
+        let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL).unwrap() {
+            VertexAttributeValues::Float32x3(normals) => normals,
+            _ => panic!("normals in wrong format"),
+        };
+        positions
+            .chunks(4)
+            .zip(normals.chunks(4))
+            .filter(|(_, normal)| normal[0][0] == if positive { 1.0 } else { -1.0 })
+            .map(|(quad, _)| {
+                let y = quad.iter().map(|p| p[1]);
+                let z = quad.iter().map(|p| p[2]);
+                (
+                    quad[0][0],
+                    y.clone().fold(f32::MAX, f32::min),
+                    y.fold(f32::MIN, f32::max),
+                    z.clone().fold(f32::MAX, f32::min),
+                    z.fold(f32::MIN, f32::max),
+                )
+            })
+            .collect()
+    }
+
+    fn is_covered_by(quads: &[(f32, f32, f32, f32, f32)], x: f32, y: f32, z: f32) -> bool {
+        quads.iter().any(|(qx, y_min, y_max, z_min, z_max)| {
+            (*qx - x).abs() < 0.001 && *y_min < y && y < *y_max && *z_min < z && z < *z_max
+        })
+    }
+
+    #[test]
+    fn no_holes_between_chunks_of_different_lods() {
+        let left_boundary = ChunkBoundaries::aligned(VoxelPosition::new(0, 0, 0));
+        let right_boundary = left_boundary.in_direction([1, 0, 0]);
+        let border = right_boundary.min[0] as f32 - HALF_VOXEL_SIZE;
+
+        for ((left_lod, right_lod), mesher) in iproduct!(
+            vec![(1, 2), (2, 1), (2, 4), (8, 4)],
+            vec![ChunkMesher::Faces, ChunkMesher::Greedy]
+        ) {
+            let left = terrain_chunk(left_boundary, left_lod);
+            let right = terrain_chunk(right_boundary, right_lod);
+            let mut access = VoxelAccess::new();
+            access.add_chunk(left_boundary, Entity::new(1), left.clone());
+            access.add_chunk(right_boundary, Entity::new(2), right.clone());
+
+            let left_quads = x_quads(
+                &mesher.mesh(
+                    &left,
+                    &ChunkNeighbours::from_access(&access, &left_boundary),
+                ),
+                true,
+            );
+            let right_quads = x_quads(
+                &mesher.mesh(
+                    &right,
+                    &ChunkNeighbours::from_access(&access, &right_boundary),
+                ),
+                false,
+            );
+
+            // sample the border plane between the voxel corners
+            for (y, z) in iproduct!(0..CHUNK_SIZE * 2, 0..CHUNK_SIZE * 2) {
+                let y = y as f32 * HALF_VOXEL_SIZE - 0.25;
+                let z = z as f32 * HALF_VOXEL_SIZE - 0.25;
+                let voxel_y = (y + HALF_VOXEL_SIZE).floor() as i32;
+                let voxel_z = (z + HALF_VOXEL_SIZE).floor() as i32;
+                let left_solid =
+                    is_rendered_solid(&left, [left_boundary.max[0] - 1, voxel_y, voxel_z]);
+                let right_solid =
+                    is_rendered_solid(&right, [right_boundary.min[0], voxel_y, voxel_z]);
+
+                if left_solid && !right_solid {
+                    assert!(
+                        is_covered_by(&left_quads, border, y, z),
+                        "hole in left chunk at y {} z {} for lods {} {} with {:?}",
+                        y,
+                        z,
+                        left_lod,
+                        right_lod,
+                        mesher
+                    );
+                }
+                if right_solid && !left_solid {
+                    assert!(
+                        is_covered_by(&right_quads, border, y, z),
+                        "hole in right chunk at y {} z {} for lods {} {} with {:?}",
+                        y,
+                        z,
+                        left_lod,
+                        right_lod,
+                        mesher
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,9 +1,12 @@
 use std::sync::Arc;
 
+use ahash::AHashSet;
 use bevy::prelude::*;
 
 use crate::{
     access::VoxelAccess,
+    boundaries::ChunkBoundaries,
+    edit_log::EditSource,
     voxel::{Voxel, VoxelPosition},
 };
 
@@ -16,8 +19,115 @@ pub struct DelayedWorldTransformations {
     pub transformations: Vec<(Timer, WorldUpdateEvent)>,
 }
 
+/// change of a single voxel, positions outside of the loaded chunks are ignored
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VoxelOperation {
+    /// inserts the voxel or overwrites the type of the voxel at its position
+    Set(Voxel),
+    /// changes the type of an existing voxel, empty positions stay empty
+    Replace(Voxel),
+    Delete(VoxelPosition),
+}
+
+impl VoxelOperation {
+    pub fn position(&self) -> VoxelPosition {
+        match self {
+            VoxelOperation::Set(voxel) | VoxelOperation::Replace(voxel) => voxel.position,
+            VoxelOperation::Delete(position) => *position,
+        }
+    }
+}
+
+/// A batch of voxel operations, computed from the current state of the world when the event is applied.
 #[derive(Clone)]
 pub struct WorldUpdateEvent {
-    pub delete: Arc<dyn Fn(&VoxelAccess) -> Vec<VoxelPosition> + Send + Sync>,
-    pub replace: bool,
+    pub operations: Arc<dyn Fn(&VoxelAccess) -> Vec<VoxelOperation> + Send + Sync>,
+    /// deleted voxels are spawned as FreeFloatingVoxel
+    pub detach: bool,
+    /// set voxels that are not connected to the ground are detached again
+    pub detach_floating: bool,
+    /// the applied changes are recorded in the EditLog with this source, only player edits are undone
+    pub source: EditSource,
+}
+
+impl WorldUpdateEvent {
+    pub fn new<F>(operations: F, detach: bool) -> WorldUpdateEvent
+    where
+        F: Fn(&VoxelAccess) -> Vec<VoxelOperation> + Send + Sync + 'static,
+    {
+        WorldUpdateEvent {
+            operations: Arc::new(operations),
+            detach,
+            detach_floating: false,
+            source: EditSource::World,
+        }
+    }
+
+    /// fixed operations, e.g. to undo an edit
+    pub fn fixed(operations: Vec<VoxelOperation>, source: EditSource) -> WorldUpdateEvent {
+        WorldUpdateEvent {
+            operations: Arc::new(move |_| operations.clone()),
+            detach: false,
+            detach_floating: false,
+            source,
+        }
+    }
+
+    /// edits are caused by the world unless marked otherwise, only player edits can be undone
+    pub fn by_player(mut self) -> WorldUpdateEvent {
+        self.source = EditSource::Player;
+        self
+    }
+
+    /// e.g. for settled voxels, which may have been put somewhere without support
+    pub fn detaching_floating(mut self) -> WorldUpdateEvent {
+        self.detach_floating = true;
+        self
+    }
+
+    pub fn deletion<F>(delete: F, detach: bool) -> WorldUpdateEvent
+    where
+        F: Fn(&VoxelAccess) -> Vec<VoxelPosition> + Send + Sync + 'static,
+    {
+        WorldUpdateEvent::new(
+            move |access| {
+                delete(access)
+                    .into_iter()
+                    .map(VoxelOperation::Delete)
+                    .collect()
+            },
+            detach,
+        )
+    }
+
+    pub fn setting<F>(set: F) -> WorldUpdateEvent
+    where
+        F: Fn(&VoxelAccess) -> Vec<Voxel> + Send + Sync + 'static,
+    {
+        WorldUpdateEvent::new(
+            move |access| set(access).into_iter().map(VoxelOperation::Set).collect(),
+            false,
+        )
+    }
+
+    pub fn replacing<F>(replace: F) -> WorldUpdateEvent
+    where
+        F: Fn(&VoxelAccess) -> Vec<Voxel> + Send + Sync + 'static,
+    {
+        WorldUpdateEvent::new(
+            move |access| {
+                replace(access)
+                    .into_iter()
+                    .map(VoxelOperation::Replace)
+                    .collect()
+            },
+            false,
+        )
+    }
+}
+
+/// chunks that have to be remeshed with the next world update, e.g. because a neighbour was generated
+#[derive(Default)]
+pub struct ChunkRemeshRequests {
+    pub boundaries: AHashSet<ChunkBoundaries>,
 }
@@ -0,0 +1,172 @@
+use strum::IntoEnumIterator;
+
+use crate::{
+    access::VoxelAccess,
+    boundaries::{ChunkBoundaries, CHUNK_SIZE},
+    chunk::VoxelChunk,
+    voxel::{VoxelDirection, VoxelPosition},
+};
+
+/*
+Snapshot of the voxel layers of the six neighbouring chunks that touch a chunk.
+Meshing runs in the background, so instead of the neighbouring chunks only the touching layers are copied.
+Neighbours that are not generated yet are treated as air.
+ */
+#[derive(Clone, Debug)]
+pub struct ChunkNeighbours {
+    boundary: ChunkBoundaries,
+    layers: Vec<Option<Vec<bool>>>,
+}
+
+impl ChunkNeighbours {
+    pub fn none(boundary: ChunkBoundaries) -> ChunkNeighbours {
+        ChunkNeighbours {
+            boundary,
+            layers: VoxelDirection::iter().map(|_| None).collect(),
+        }
+    }
+
+    pub fn from_access(voxel_access: &VoxelAccess, boundary: &ChunkBoundaries) -> ChunkNeighbours {
+        ChunkNeighbours {
+            boundary: *boundary,
+            layers: VoxelDirection::iter()
+                .map(|direction| {
+                    voxel_access
+                        .get_chunk(&boundary.in_direction(direction.offset()))
+                        .map(|neighbour| touching_layer(boundary, neighbour, direction))
+                })
+                .collect(),
+        }
+    }
+
+    /// whether the neighbour in the given direction was generated when the snapshot was taken
+    pub fn has(&self, direction: VoxelDirection) -> bool {
+        self.layers[direction as usize].is_some()
+    }
+
+    /// position has to be directly outside of the chunk
+    pub fn is_solid(&self, position: &VoxelPosition) -> bool {
+        let p = [position.x, position.y, position.z];
+        for direction in VoxelDirection::iter() {
+            let (axis, positive) = direction.axis();
+            let outside = if positive {
+                p[axis] == self.boundary.max[axis]
+            } else {
+                p[axis] == self.boundary.min[axis] - 1
+            };
+            if outside {
+                return match (&self.layers[direction as usize], self.layer_index(axis, p)) {
+                    (Some(layer), Some(i)) => layer[i],
+                    _ => false,
+                };
+            }
+        }
+        false
+    }
+
+    fn layer_index(&self, axis: usize, p: [i32; 3]) -> Option<usize> {
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let a = p[u] - self.boundary.min[u];
+        let b = p[v] - self.boundary.min[v];
+        if a < 0 || a >= CHUNK_SIZE || b < 0 || b >= CHUNK_SIZE {
+            None
+        } else {
+            Some((b * CHUNK_SIZE + a) as usize)
+        }
+    }
+}
+
+fn touching_layer(
+    boundary: &ChunkBoundaries,
+    neighbour: &VoxelChunk,
+    direction: VoxelDirection,
+) -> Vec<bool> {
+    let (axis, positive) = direction.axis();
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let mut layer = Vec::with_capacity((CHUNK_SIZE * CHUNK_SIZE) as usize);
+    if neighbour.count == 0 {
+        layer.resize((CHUNK_SIZE * CHUNK_SIZE) as usize, false);
+        return layer;
+    }
+    let mut p = [0; 3];
+    p[axis] = if positive {
+        boundary.max[axis]
+    } else {
+        boundary.min[axis] - 1
+    };
+    for b in 0..CHUNK_SIZE {
+        for a in 0..CHUNK_SIZE {
+            p[u] = boundary.min[u] + a;
+            p[v] = boundary.min[v] + b;
+            layer.push(
+                neighbour
+                    .get(&VoxelPosition {
+                        x: p[0],
+                        y: p[1],
+                        z: p[2],
+                    })
+                    .is_some(),
+            );
+        }
+    }
+    layer
+}
+
+/// chunks that share a face with the position and would have to be remeshed when it changes
+pub fn touching_chunks(position: VoxelPosition) -> Vec<ChunkBoundaries> {
+    let own = ChunkBoundaries::aligned(position);
+    VoxelDirection::iter()
+        .map(|direction| ChunkBoundaries::aligned(position.in_direction(direction)))
+        .filter(|b| *b != own)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::iproduct;
+
+    use crate::{
+        access::VoxelAccess,
+        boundaries::{ChunkBoundaries, CHUNK_SIZE},
+        chunk::VoxelChunk,
+        lod::combine_voxels,
+        voxel::{Voxel, VoxelDirection, VoxelPosition, VoxelTypes},
+    };
+    use bevy::prelude::Entity;
+
+    use super::ChunkNeighbours;
+
+    fn full_chunk(boundary: ChunkBoundaries) -> VoxelChunk {
+        let mut chunk = VoxelChunk::empty(boundary);
+        for (x, y, z) in iproduct!(0..CHUNK_SIZE, 0..CHUNK_SIZE, 0..CHUNK_SIZE) {
+            chunk.set(Voxel::new(
+                boundary.min[0] + x,
+                boundary.min[1] + y,
+                boundary.min[2] + z,
+                VoxelTypes::GreyRock2,
+            ));
+        }
+        chunk
+    }
+
+    #[test]
+    fn faces_towards_neighbours_are_culled() {
+        let boundary = ChunkBoundaries::aligned(VoxelPosition::new(0, 0, 0));
+        let right = boundary.in_direction([1, 0, 0]);
+        let mut access = VoxelAccess::new();
+        access.add_chunk(right, Entity::new(1), full_chunk(right));
+        let chunk = full_chunk(boundary);
+
+        let open = combine_voxels(&chunk, &ChunkNeighbours::none(boundary));
+        let neighbours = ChunkNeighbours::from_access(&access, &boundary);
+        let culled = combine_voxels(&chunk, &neighbours);
+
+        assert!(neighbours.has(VoxelDirection::RIGHT));
+        assert!(!neighbours.has(VoxelDirection::LEFT));
+        assert_eq!(
+            open.len() - culled.len(),
+            (CHUNK_SIZE * CHUNK_SIZE) as usize
+        );
+        assert!(culled.iter().all(|f| f.direction != VoxelDirection::RIGHT));
+    }
+}
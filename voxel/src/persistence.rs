@@ -0,0 +1,400 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use ahash::AHashMap;
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    access::VoxelAccess,
+    boundaries::{ChunkBoundaries, CHUNK_SIZE},
+    chunk::VoxelChunk,
+    voxel::{Voxel, VoxelPosition, VoxelTypes},
+    world_gen::WorldSeed,
+};
+
+/// number of chunks along each axis that are stored in the same region file
+pub const REGION_SIZE: i32 = 8;
+
+const REGION_VERSION: u32 = 1;
+const AIR: u8 = u8::MAX;
+
+/*
+Region file layout (little endian):
+    u32 version
+    u32 number of chunks
+    per chunk: [i32; 3] chunk min, u32 byte length, run length encoded voxels
+
+The voxels of a chunk are stored as (u16 run length, u8 type) pairs in x, y, z order.
+The lod of a chunk is not stored, it is recomputed from the player position after loading.
+
+Every region file is read once, afterwards the encoded chunks are kept in memory and updated when saving.
+ */
+#[derive(Clone)]
+pub struct ChunkStore {
+    directory: PathBuf,
+    /// encoded chunks by region, a region without a file is cached as empty
+    regions: Arc<Mutex<AHashMap<[i32; 3], AHashMap<[i32; 3], Vec<u8>>>>>,
+}
+
+pub struct ChunkSaveTimer {
+    pub timer: Timer,
+}
+
+impl ChunkStore {
+    pub fn new<P: Into<PathBuf>>(directory: P) -> ChunkStore {
+        ChunkStore {
+            directory: directory.into(),
+            regions: Arc::new(Mutex::new(AHashMap::new())),
+        }
+    }
+
+    /// every seed gets its own directory, so chunks of different worlds are never mixed
+    pub fn for_seed(seed: &WorldSeed) -> ChunkStore {
+        ChunkStore::new(format!("saves/world_{}", seed.seed))
+    }
+
+    pub fn load(&self, boundaries: &ChunkBoundaries) -> Option<VoxelChunk> {
+        let region = region_of(boundaries);
+        let bytes = {
+            let mut regions = self.regions.lock().unwrap();
+            match self.cached_region(&mut regions, region) {
+                Ok(chunks) => chunks.get(&boundaries.min).cloned()?,
+                Err(e) => {
+                    warn!("Could not read region {:?}: {}", region, e);
+                    return None;
+                }
+            }
+        };
+        // decoded without holding the lock, so generation tasks only wait for each other to read region files
+        match decode_chunk(*boundaries, &bytes) {
+            Ok(chunk) => Some(chunk),
+            Err(e) => {
+                warn!("Could not decode saved chunk {:?}: {}", boundaries, e);
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, chunks: &[&VoxelChunk]) -> io::Result<()> {
+        let mut regions = self.regions.lock().unwrap();
+        fs::create_dir_all(&self.directory)?;
+
+        let mut by_region: AHashMap<[i32; 3], Vec<&VoxelChunk>> = AHashMap::new();
+        for chunk in chunks {
+            by_region
+                .entry(region_of(&chunk.boundary))
+                .or_insert(vec![])
+                .push(*chunk);
+        }
+
+        for (region, region_chunks) in by_region {
+            let stored = self.cached_region(&mut regions, region)?;
+            for chunk in region_chunks {
+                stored.insert(chunk.boundary.min, encode_chunk(chunk));
+            }
+            self.write_region(region, stored)?;
+        }
+        Ok(())
+    }
+
+    /// reads the region file the first time the region is used, failed reads are not cached
+    fn cached_region<'a>(
+        &self,
+        regions: &'a mut AHashMap<[i32; 3], AHashMap<[i32; 3], Vec<u8>>>,
+        region: [i32; 3],
+    ) -> io::Result<&'a mut AHashMap<[i32; 3], Vec<u8>>> {
+        if !regions.contains_key(&region) {
+            let chunks = self.read_region(region)?;
+            regions.insert(region, chunks);
+        }
+        Ok(regions.get_mut(&region).unwrap())
+    }
+
+    fn region_path(&self, region: [i32; 3]) -> PathBuf {
+        self.directory.join(format!(
+            "r.{}.{}.{}.region",
+            region[0], region[1], region[2]
+        ))
+    }
+
+    fn read_region(&self, region: [i32; 3]) -> io::Result<AHashMap<[i32; 3], Vec<u8>>> {
+        let mut chunks = AHashMap::new();
+        let bytes = match fs::read(self.region_path(region)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(chunks),
+            Err(e) => return Err(e),
+        };
+        let mut reader = bytes.as_slice();
+        let version = read_u32(&mut reader)?;
+        if version != REGION_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported region version {}", version),
+            ));
+        }
+        let count = read_u32(&mut reader)?;
+        for _ in 0..count {
+            let min = [
+                read_i32(&mut reader)?,
+                read_i32(&mut reader)?,
+                read_i32(&mut reader)?,
+            ];
+            let length = read_u32(&mut reader)? as usize;
+            let mut chunk_bytes = vec![0u8; length];
+            reader.read_exact(&mut chunk_bytes)?;
+            chunks.insert(min, chunk_bytes);
+        }
+        Ok(chunks)
+    }
+
+    fn write_region(
+        &self,
+        region: [i32; 3],
+        chunks: &AHashMap<[i32; 3], Vec<u8>>,
+    ) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        bytes.write_all(&REGION_VERSION.to_le_bytes())?;
+        bytes.write_all(&(chunks.len() as u32).to_le_bytes())?;
+        for (min, chunk_bytes) in chunks.iter() {
+            for v in min.iter() {
+                bytes.write_all(&v.to_le_bytes())?;
+            }
+            bytes.write_all(&(chunk_bytes.len() as u32).to_le_bytes())?;
+            bytes.write_all(chunk_bytes)?;
+        }
+
+        // write to a temporary file first, so a crash while saving does not corrupt the region
+        let path = self.region_path(region);
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, bytes)?;
+        fs::rename(tmp_path, path)
+    }
+}
+
+fn region_of(boundaries: &ChunkBoundaries) -> [i32; 3] {
+    let region_length = CHUNK_SIZE * REGION_SIZE;
+    [
+        boundaries.min[0].div_euclid(region_length),
+        boundaries.min[1].div_euclid(region_length),
+        boundaries.min[2].div_euclid(region_length),
+    ]
+}
+
+pub fn encode_chunk(chunk: &VoxelChunk) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut current: Option<(u8, u16)> = None;
+    for z in chunk.boundary.min[2]..chunk.boundary.max[2] {
+        for y in chunk.boundary.min[1]..chunk.boundary.max[1] {
+            for x in chunk.boundary.min[0]..chunk.boundary.max[0] {
+                let typ = chunk
+                    .get(&VoxelPosition { x, y, z })
+                    .map(|t| t.to_u8())
+                    .unwrap_or(AIR);
+                current = match current {
+                    Some((current_typ, run)) if current_typ == typ && run < u16::MAX => {
+                        Some((typ, run + 1))
+                    }
+                    Some((current_typ, run)) => {
+                        push_run(&mut bytes, current_typ, run);
+                        Some((typ, 1))
+                    }
+                    None => Some((typ, 1)),
+                };
+            }
+        }
+    }
+    if let Some((typ, run)) = current {
+        push_run(&mut bytes, typ, run);
+    }
+    bytes
+}
+
+fn push_run(bytes: &mut Vec<u8>, typ: u8, run: u16) {
+    bytes.extend_from_slice(&run.to_le_bytes());
+    bytes.push(typ);
+}
+
+pub fn decode_chunk(boundary: ChunkBoundaries, bytes: &[u8]) -> io::Result<VoxelChunk> {
+    let mut chunk = VoxelChunk::empty(boundary);
+    let mut reader = bytes;
+    let mut i: i32 = 0;
+    let total = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+    while !reader.is_empty() {
+        let mut run_bytes = [0u8; 2];
+        reader.read_exact(&mut run_bytes)?;
+        let run = u16::from_le_bytes(run_bytes) as i32;
+        let mut typ_byte = [0u8; 1];
+        reader.read_exact(&mut typ_byte)?;
+        if i + run > total {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "chunk contains more voxels than fit into its boundary",
+            ));
+        }
+        if typ_byte[0] != AIR {
+            let typ = VoxelTypes::from_u8(typ_byte[0]).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown voxel type {}", typ_byte[0]),
+                )
+            })?;
+            for j in i..i + run {
+                chunk.set(Voxel {
+                    position: VoxelPosition {
+                        x: boundary.min[0] + j % CHUNK_SIZE,
+                        y: boundary.min[1] + (j / CHUNK_SIZE) % CHUNK_SIZE,
+                        z: boundary.min[2] + j / (CHUNK_SIZE * CHUNK_SIZE),
+                    },
+                    typ,
+                });
+            }
+        }
+        i += run;
+    }
+    if i != total {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "chunk is truncated",
+        ));
+    }
+    Ok(chunk)
+}
+
+fn read_u32(reader: &mut &[u8]) -> io::Result<u32> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(u32::from_le_bytes(buffer))
+}
+
+fn read_i32(reader: &mut &[u8]) -> io::Result<i32> {
+    let mut buffer = [0u8; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(i32::from_le_bytes(buffer))
+}
+
+/// inserted at startup instead of in the plugin, so a WorldSeed inserted by the app is used
+pub fn setup_chunk_store(mut commands: Commands, seed: Res<WorldSeed>) {
+    commands.insert_resource(ChunkStore::for_seed(&seed));
+}
+
+/// runs in CoreStage::Last, so an AppExit sent during Update is seen before the app stops
+pub fn save_modified_chunks(
+    mut chunk_access: ResMut<VoxelAccess>,
+    store: Res<ChunkStore>,
+    mut save_timer: ResMut<ChunkSaveTimer>,
+    mut exit_events: EventReader<AppExit>,
+    time: Res<Time>,
+) {
+    let exiting = exit_events.iter().next().is_some();
+    if !save_timer.timer.tick(time.delta()).just_finished() && !exiting {
+        return;
+    }
+
+    let dirty: Vec<&VoxelChunk> = chunk_access
+        .iter()
+        .map(|(_, (_, chunk))| chunk)
+        .filter(|chunk| chunk.dirty)
+        .collect();
+    if dirty.is_empty() {
+        return;
+    }
+
+    match store.save(&dirty) {
+        Ok(()) => {
+            for (_, (_, chunk)) in chunk_access.iter_mut() {
+                chunk.dirty = false;
+            }
+        }
+        Err(e) => warn!("Could not save modified chunks: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        boundaries::ChunkBoundaries,
+        chunk::VoxelChunk,
+        voxel::{Voxel, VoxelPosition, VoxelTypes},
+    };
+
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::{decode_chunk, encode_chunk, ChunkStore};
+
+    fn test_voxels() -> Vec<Voxel> {
+        vec![
+            Voxel::new(-1, 64, 0, VoxelTypes::Moss),
+            Voxel::new(-64, 127, 63, VoxelTypes::Snow),
+            Voxel::new(-30, 100, 10, VoxelTypes::BrownRock),
+        ]
+    }
+
+    fn test_chunk() -> VoxelChunk {
+        let boundaries = ChunkBoundaries::aligned(VoxelPosition {
+            x: -10,
+            y: 70,
+            z: 5,
+        });
+        let mut chunk = VoxelChunk::empty(boundaries);
+        chunk.lod = 4;
+        for voxel in test_voxels() {
+            chunk.set(voxel);
+        }
+        chunk
+    }
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let chunk = test_chunk();
+        let decoded = decode_chunk(chunk.boundary, &encode_chunk(&chunk)).unwrap();
+
+        assert_eq!(decoded.count, 3);
+        assert_eq!(decoded.lod, 1);
+        for voxel in test_voxels() {
+            assert_eq!(decoded.get(&voxel.position), Some(voxel.typ));
+        }
+    }
+
+    #[test]
+    fn decode_rejects_truncated_chunk() {
+        let chunk = test_chunk();
+        let bytes = encode_chunk(&chunk);
+
+        assert!(decode_chunk(chunk.boundary, &bytes[..bytes.len() - 3]).is_err());
+    }
+
+    #[test]
+    fn save_and_load_from_region() {
+        let directory = std::env::temp_dir().join(format!(
+            "voxel_persistence_test_{}_{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let _ = std::fs::remove_dir_all(&directory);
+        let store = ChunkStore::new(&directory);
+        let chunk = test_chunk();
+        let other = VoxelChunk::empty(chunk.boundary.in_direction([1, 0, 0]));
+        assert!(store.load(&chunk.boundary).is_none());
+
+        store.save(&[&chunk, &other]).unwrap();
+
+        let loaded = store.load(&chunk.boundary).unwrap();
+        assert_eq!(loaded.count, chunk.count);
+        assert_eq!(store.load(&other.boundary).unwrap().count, 0);
+        assert!(store
+            .load(&chunk.boundary.in_direction([0, 1, 0]))
+            .is_none());
+        // a new store reads the region file instead of the cache
+        let reopened = ChunkStore::new(&directory);
+        assert_eq!(reopened.load(&chunk.boundary).unwrap().count, chunk.count);
+
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
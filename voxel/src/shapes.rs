@@ -0,0 +1,259 @@
+use bevy::prelude::Vec3;
+use noise::{NoiseFn, Perlin, Seedable};
+
+use crate::voxel::{VoxelPosition, VOXEL_SIZE};
+
+/*
+Shapes in world space that can be voxelized, e.g. as brushes for explosions, digging or structures.
+A voxel belongs to a shape if its center is inside of the shape or on its surface.
+ */
+pub trait VoxelShape {
+    /// negative inside of the shape, exact on the surface, may be an approximation elsewhere
+    fn distance(&self, point: Vec3) -> f32;
+
+    /// min and max corner of a box containing the shape
+    fn bounds(&self) -> (Vec3, Vec3);
+
+    fn contains(&self, point: Vec3) -> bool {
+        self.distance(point) <= 0.0
+    }
+
+    fn voxels(&self) -> Vec<VoxelPosition> {
+        let (min, max) = self.bounds();
+        let min = (min / VOXEL_SIZE).floor();
+        let max = (max / VOXEL_SIZE).ceil();
+        let mut voxels = Vec::new();
+        for x in min.x as i32..=max.x as i32 {
+            for y in min.y as i32..=max.y as i32 {
+                for z in min.z as i32..=max.z as i32 {
+                    let position = VoxelPosition::new(x, y, z);
+                    if self.contains(position.to_vec()) {
+                        voxels.push(position);
+                    }
+                }
+            }
+        }
+        voxels
+    }
+
+    /// perturbs the surface by up to amplitude in both directions
+    fn noisy(self, seed: u32, wavelength: f64, amplitude: f32) -> Noisy<Self>
+    where
+        Self: Sized,
+    {
+        Noisy {
+            shape: self,
+            noise: Perlin::new().set_seed(seed),
+            wavelength,
+            amplitude,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl VoxelShape for Sphere {
+    fn distance(&self, point: Vec3) -> f32 {
+        point.distance(self.center) - self.radius
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        (
+            self.center - Vec3::splat(self.radius),
+            self.center + Vec3::splat(self.radius),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Ellipsoid {
+    pub center: Vec3,
+    pub radii: Vec3,
+}
+
+impl VoxelShape for Ellipsoid {
+    fn distance(&self, point: Vec3) -> f32 {
+        let scaled = (point - self.center) / self.radii;
+        (scaled.length() - 1.0) * self.radii.min_element()
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        (self.center - self.radii, self.center + self.radii)
+    }
+}
+
+/// upright cylinder standing on base
+#[derive(Clone, Copy, Debug)]
+pub struct Cylinder {
+    pub base: Vec3,
+    pub radius: f32,
+    pub height: f32,
+}
+
+impl VoxelShape for Cylinder {
+    fn distance(&self, point: Vec3) -> f32 {
+        let offset = point - self.base;
+        let radial = Vec3::new(offset.x, 0.0, offset.z).length() - self.radius;
+        let vertical = (offset.y - self.height / 2.0).abs() - self.height / 2.0;
+        let outside = Vec3::new(radial.max(0.0), vertical.max(0.0), 0.0).length();
+        outside + radial.max(vertical).min(0.0)
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        (
+            self.base - Vec3::new(self.radius, 0.0, self.radius),
+            self.base + Vec3::new(self.radius, self.height, self.radius),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Cuboid {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl VoxelShape for Cuboid {
+    fn distance(&self, point: Vec3) -> f32 {
+        let center = (self.min + self.max) / 2.0;
+        let half_size = (self.max - self.min) / 2.0;
+        let q = (point - center).abs() - half_size;
+        q.max(Vec3::ZERO).length() + q.max_element().min(0.0)
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        (self.min, self.max)
+    }
+}
+
+/// all points within radius of the segment from start to end
+#[derive(Clone, Copy, Debug)]
+pub struct Capsule {
+    pub start: Vec3,
+    pub end: Vec3,
+    pub radius: f32,
+}
+
+impl VoxelShape for Capsule {
+    fn distance(&self, point: Vec3) -> f32 {
+        let segment = self.end - self.start;
+        let length_sq = segment.length_squared();
+        let t = if length_sq == 0.0 {
+            0.0
+        } else {
+            ((point - self.start).dot(segment) / length_sq).clamp(0.0, 1.0)
+        };
+        point.distance(self.start + segment * t) - self.radius
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        (
+            self.start.min(self.end) - Vec3::splat(self.radius),
+            self.start.max(self.end) + Vec3::splat(self.radius),
+        )
+    }
+}
+
+/// shape with a surface displaced by 3D perlin noise, wavelength is the size of the noise features in voxels
+#[derive(Clone, Debug)]
+pub struct Noisy<S> {
+    pub shape: S,
+    pub noise: Perlin,
+    pub wavelength: f64,
+    pub amplitude: f32,
+}
+
+impl<S: VoxelShape> VoxelShape for Noisy<S> {
+    fn distance(&self, point: Vec3) -> f32 {
+        let noise = self
+            .noise
+            .get([
+                point.x as f64 / self.wavelength,
+                point.y as f64 / self.wavelength,
+                point.z as f64 / self.wavelength,
+            ])
+            .max(-1.0)
+            .min(1.0);
+        self.shape.distance(point) + noise as f32 * self.amplitude
+    }
+
+    fn bounds(&self) -> (Vec3, Vec3) {
+        let (min, max) = self.shape.bounds();
+        (
+            min - Vec3::splat(self.amplitude),
+            max + Vec3::splat(self.amplitude),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Vec3;
+
+    use super::{Capsule, Cuboid, Cylinder, Ellipsoid, Sphere, VoxelShape};
+
+    #[test]
+    fn sphere_voxel_counts() {
+        for (radius, count) in [(0.0, 1), (1.0, 7), (2.0, 33)].iter() {
+            let sphere = Sphere {
+                center: Vec3::new(3.0, -4.0, 5.0),
+                radius: *radius,
+            };
+            assert_eq!(sphere.voxels().len(), *count);
+        }
+    }
+
+    #[test]
+    fn shape_voxel_counts() {
+        let ellipsoid = Ellipsoid {
+            center: Vec3::ZERO,
+            radii: Vec3::new(2.0, 1.0, 1.0),
+        };
+        let cylinder = Cylinder {
+            base: Vec3::new(1.0, 2.0, 3.0),
+            radius: 1.0,
+            height: 2.0,
+        };
+        let cuboid = Cuboid {
+            min: Vec3::ZERO,
+            max: Vec3::new(2.0, 3.0, 4.0),
+        };
+        let capsule = Capsule {
+            start: Vec3::ZERO,
+            end: Vec3::new(3.0, 0.0, 0.0),
+            radius: 1.0,
+        };
+
+        assert_eq!(ellipsoid.voxels().len(), 9);
+        assert_eq!(cylinder.voxels().len(), 3 * 5);
+        assert_eq!(cuboid.voxels().len(), 3 * 4 * 5);
+        assert_eq!(capsule.voxels().len(), 4 * 5 + 2);
+    }
+
+    #[test]
+    fn noise_perturbs_the_surface_within_the_amplitude() {
+        let sphere = Sphere {
+            center: Vec3::ZERO,
+            radius: 8.0,
+        };
+        let plain = sphere.voxels();
+        let noisy = sphere.noisy(3, 4.0, 2.0).voxels();
+        let inner = Sphere {
+            radius: 6.0,
+            ..sphere
+        };
+        let outer = Sphere {
+            radius: 10.0,
+            ..sphere
+        };
+
+        assert_ne!(plain, noisy);
+        assert!(inner.voxels().iter().all(|p| noisy.contains(p)));
+        assert!(noisy.iter().all(|p| outer.contains(p.to_vec())));
+        assert_eq!(sphere.noisy(3, 4.0, 0.0).voxels(), plain);
+    }
+}
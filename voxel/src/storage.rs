@@ -0,0 +1,165 @@
+use std::mem::size_of;
+
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics},
+    prelude::*,
+};
+
+use crate::{access::VoxelAccess, boundaries::CHUNK_SIZE, voxel::VoxelTypes};
+
+pub const VOXELS_PER_CHUNK: usize = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+pub const VOXEL_MEMORY: DiagnosticId =
+    DiagnosticId::from_u128(93427283402108410358205133478231750912);
+
+/*
+A chunk is either filled with a single value (air or one voxel type) or stores a palette of the values in use.
+For the paletted case every voxel is a bit packed index into the palette. The palette also keeps track of how
+often each entry is used, so entries can be reused and the storage collapses back to a uniform chunk.
+ */
+#[derive(Debug, Clone)]
+pub enum VoxelStorage {
+    Uniform(Option<VoxelTypes>),
+    Paletted(PalettedVoxels),
+}
+
+#[derive(Debug, Clone)]
+pub struct PalettedVoxels {
+    palette: Vec<Option<VoxelTypes>>,
+    counts: Vec<usize>,
+    bits: usize,
+    data: Vec<u64>,
+}
+
+impl VoxelStorage {
+    pub fn get(&self, i: usize) -> Option<VoxelTypes> {
+        match self {
+            VoxelStorage::Uniform(value) => *value,
+            VoxelStorage::Paletted(paletted) => paletted.get(i),
+        }
+    }
+
+    /// returns the value that was stored at i before
+    pub fn set(&mut self, i: usize, value: Option<VoxelTypes>) -> Option<VoxelTypes> {
+        match self {
+            VoxelStorage::Uniform(current) => {
+                let previous = *current;
+                if previous != value {
+                    let mut paletted = PalettedVoxels::filled(previous);
+                    paletted.set(i, value);
+                    *self = VoxelStorage::Paletted(paletted);
+                }
+                previous
+            }
+            VoxelStorage::Paletted(paletted) => {
+                let previous = paletted.set(i, value);
+                if let Some(uniform) = paletted.uniform() {
+                    *self = VoxelStorage::Uniform(uniform);
+                }
+                previous
+            }
+        }
+    }
+
+    pub fn is_uniform(&self) -> bool {
+        matches!(self, VoxelStorage::Uniform(_))
+    }
+
+    /// bytes allocated on the heap
+    pub fn heap_size(&self) -> usize {
+        match self {
+            VoxelStorage::Uniform(_) => 0,
+            VoxelStorage::Paletted(paletted) => {
+                paletted.data.capacity() * size_of::<u64>()
+                    + paletted.palette.capacity() * size_of::<Option<VoxelTypes>>()
+                    + paletted.counts.capacity() * size_of::<usize>()
+            }
+        }
+    }
+}
+
+impl PalettedVoxels {
+    fn filled(value: Option<VoxelTypes>) -> PalettedVoxels {
+        PalettedVoxels {
+            palette: vec![value],
+            counts: vec![VOXELS_PER_CHUNK],
+            bits: 1,
+            data: vec![0; VOXELS_PER_CHUNK / 64],
+        }
+    }
+
+    fn get(&self, i: usize) -> Option<VoxelTypes> {
+        self.palette[read_packed(&self.data, self.bits, i)]
+    }
+
+    fn set(&mut self, i: usize, value: Option<VoxelTypes>) -> Option<VoxelTypes> {
+        let previous_index = read_packed(&self.data, self.bits, i);
+        let previous = self.palette[previous_index];
+        if previous != value {
+            let index = self.palette_index(value);
+            self.counts[previous_index] -= 1;
+            self.counts[index] += 1;
+            write_packed(&mut self.data, self.bits, i, index);
+        }
+        previous
+    }
+
+    fn palette_index(&mut self, value: Option<VoxelTypes>) -> usize {
+        if let Some(i) = self.palette.iter().position(|p| *p == value) {
+            return i;
+        }
+        if let Some(i) = self.counts.iter().position(|c| *c == 0) {
+            self.palette[i] = value;
+            return i;
+        }
+        self.palette.push(value);
+        self.counts.push(0);
+        if self.palette.len() > 1 << self.bits {
+            self.grow();
+        }
+        self.palette.len() - 1
+    }
+
+    fn grow(&mut self) {
+        let bits = self.bits * 2;
+        let mut data = vec![0; VOXELS_PER_CHUNK * bits / 64];
+        for i in 0..VOXELS_PER_CHUNK {
+            write_packed(&mut data, bits, i, read_packed(&self.data, self.bits, i));
+        }
+        self.data = data;
+        self.bits = bits;
+    }
+
+    fn uniform(&self) -> Option<Option<VoxelTypes>> {
+        self.counts
+            .iter()
+            .position(|c| *c == VOXELS_PER_CHUNK)
+            .map(|i| self.palette[i])
+    }
+}
+
+fn read_packed(data: &[u64], bits: usize, i: usize) -> usize {
+    let per_word = 64 / bits;
+    let shift = (i % per_word) * bits;
+    ((data[i / per_word] >> shift) & ((1u64 << bits) - 1)) as usize
+}
+
+fn write_packed(data: &mut [u64], bits: usize, i: usize, index: usize) {
+    let per_word = 64 / bits;
+    let shift = (i % per_word) * bits;
+    let mask = ((1u64 << bits) - 1) << shift;
+    let word = &mut data[i / per_word];
+    *word = (*word & !mask) | ((index as u64) << shift);
+}
+
+pub fn setup_voxel_memory_diagnostic(mut diagnostics: ResMut<Diagnostics>) {
+    diagnostics.add(Diagnostic::new(VOXEL_MEMORY, "voxel_memory_mb", 20));
+}
+
+pub fn measure_voxel_memory(mut diagnostics: ResMut<Diagnostics>, voxel_access: Res<VoxelAccess>) {
+    let bytes: usize = voxel_access
+        .iter()
+        .map(|(_, (_, chunk))| chunk.memory_usage())
+        .sum();
+    diagnostics.add_measurement(VOXEL_MEMORY, bytes as f64 / (1024.0 * 1024.0));
+}
@@ -1,6 +1,9 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 use strum_macros::EnumIter;
 
+use crate::shapes::{Sphere, VoxelShape};
+
 pub const HALF_VOXEL_SIZE: f32 = 0.5f32;
 pub const VOXEL_SIZE: f32 = HALF_VOXEL_SIZE * 2.0f32;
 
@@ -14,6 +17,44 @@ pub enum VoxelDirection {
     BACK,
 }
 
+impl VoxelDirection {
+    pub fn offset(&self) -> [i32; 3] {
+        match self {
+            VoxelDirection::UP => [0, 1, 0],
+            VoxelDirection::DOWN => [0, -1, 0],
+            VoxelDirection::LEFT => [-1, 0, 0],
+            VoxelDirection::RIGHT => [1, 0, 0],
+            VoxelDirection::FRONT => [0, 0, -1],
+            VoxelDirection::BACK => [0, 0, 1],
+        }
+    }
+
+    /// the axis (0 = x, 1 = y, 2 = z) and whether the direction points towards positive values
+    pub fn axis(&self) -> (usize, bool) {
+        match self {
+            VoxelDirection::UP => (1, true),
+            VoxelDirection::DOWN => (1, false),
+            VoxelDirection::LEFT => (0, false),
+            VoxelDirection::RIGHT => (0, true),
+            VoxelDirection::FRONT => (2, false),
+            VoxelDirection::BACK => (2, true),
+        }
+    }
+
+    /// inverse of axis
+    pub fn from_axis(axis: usize, positive: bool) -> VoxelDirection {
+        match (axis, positive) {
+            (0, false) => VoxelDirection::LEFT,
+            (0, true) => VoxelDirection::RIGHT,
+            (1, false) => VoxelDirection::DOWN,
+            (1, true) => VoxelDirection::UP,
+            (2, false) => VoxelDirection::FRONT,
+            (2, true) => VoxelDirection::BACK,
+            _ => panic!("there is no axis {}", axis),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct VoxelFace {
     pub direction: VoxelDirection,
@@ -38,7 +79,7 @@ impl VoxelFace {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize)]
 pub struct VoxelPosition {
     pub x: i32,
     pub y: i32,
@@ -165,18 +206,13 @@ impl VoxelPosition {
         (min, max)
     }
 
+    /// voxels with their center within radius of center
     pub fn sphere(center: &Vec3, radius: f32) -> Vec<VoxelPosition> {
-        let center_voxel = VoxelPosition::from_vec3(center);
-        let voxel_radius = (radius / VOXEL_SIZE) as i32;
-        let mut voxels = Vec::with_capacity((voxel_radius + voxel_radius * voxel_radius) as usize);
-        for x in center_voxel.x - voxel_radius..center_voxel.x + voxel_radius {
-            for y in center_voxel.y - voxel_radius..center_voxel.y + voxel_radius {
-                for z in center_voxel.z - voxel_radius..center_voxel.z + voxel_radius {
-                    voxels.push(VoxelPosition { x, y, z });
-                }
-            }
+        Sphere {
+            center: *center,
+            radius,
         }
-        voxels
+        .voxels()
     }
 }
 
@@ -249,7 +285,7 @@ impl Voxel {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Copy, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Copy, Hash, Serialize, Deserialize)]
 pub enum VoxelTypes {
     Moss,
     DarkRock1,
@@ -260,3 +296,32 @@ pub enum VoxelTypes {
     GroundRock1,
     Snow,
 }
+
+impl VoxelTypes {
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            VoxelTypes::Moss => 0,
+            VoxelTypes::DarkRock1 => 1,
+            VoxelTypes::GreyRock1 => 2,
+            VoxelTypes::GreyRock2 => 3,
+            VoxelTypes::BrownRock => 4,
+            VoxelTypes::DarkRock2 => 5,
+            VoxelTypes::GroundRock1 => 6,
+            VoxelTypes::Snow => 7,
+        }
+    }
+
+    pub fn from_u8(v: u8) -> Option<VoxelTypes> {
+        match v {
+            0 => Some(VoxelTypes::Moss),
+            1 => Some(VoxelTypes::DarkRock1),
+            2 => Some(VoxelTypes::GreyRock1),
+            3 => Some(VoxelTypes::GreyRock2),
+            4 => Some(VoxelTypes::BrownRock),
+            5 => Some(VoxelTypes::DarkRock2),
+            6 => Some(VoxelTypes::GroundRock1),
+            7 => Some(VoxelTypes::Snow),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use rand::{prelude::SmallRng, Rng};
+use smallvec::SmallVec;
+
+use crate::voxel::{VoxelPosition, VoxelTypes};
+
+use super::{
+    config::{BiomeConfig, WorldGenConfig},
+    height::HeightGen,
+    noise_sampler::NoiseSampler,
+    seed::WorldSeed,
+    type_decision::VoxelTypeDecision,
+};
+
+/// raw noise of the climate samplers at a position, each value within [-1, 1]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Climate {
+    pub temperature: f64,
+    pub humidity: f64,
+    pub continentalness: f64,
+}
+
+impl Climate {
+    fn distance(&self, other: &Climate) -> f64 {
+        ((self.temperature - other.temperature).powi(2)
+            + (self.humidity - other.humidity).powi(2)
+            + (self.continentalness - other.continentalness).powi(2))
+        .sqrt()
+    }
+}
+
+#[derive(Clone)]
+pub struct Biome {
+    pub name: String,
+    pub climate: Climate,
+    height: HeightGen,
+    types: VoxelTypeDecision,
+}
+
+impl Biome {
+    fn new(seed: &WorldSeed, config: &BiomeConfig) -> Biome {
+        Biome {
+            name: config.name.clone(),
+            climate: Climate {
+                temperature: config.temperature,
+                humidity: config.humidity,
+                continentalness: config.continentalness,
+            },
+            height: HeightGen::new(seed, &config.height),
+            types: VoxelTypeDecision::new(&config.voxel_types),
+        }
+    }
+}
+
+/// indices of the biomes influencing a column with their weights, which sum up to 1, the largest weight first
+pub type BiomeWeights = SmallVec<[(usize, f64); 4]>;
+
+/*
+Every biome is centered on a point in the climate space. A column belongs to the biome closest to its climate.
+Biomes whose climate distance is less than the blend distance further away than the closest one contribute
+to the height of the column as well, with a weight falling off linearly with the additional distance.
+This keeps the terrain continuous across biome borders.
+ */
+#[derive(Clone)]
+pub struct BiomeMap {
+    temperature: NoiseSampler,
+    humidity: NoiseSampler,
+    continentalness: NoiseSampler,
+    blend: f64,
+    biomes: Vec<Biome>,
+}
+
+impl BiomeMap {
+    pub fn new(seed: &WorldSeed, config: &WorldGenConfig) -> BiomeMap {
+        BiomeMap {
+            temperature: NoiseSampler::new(seed, &config.climate.temperature),
+            humidity: NoiseSampler::new(seed, &config.climate.humidity),
+            continentalness: NoiseSampler::new(seed, &config.climate.continentalness),
+            blend: config.biome_blend,
+            biomes: config.biomes.iter().map(|b| Biome::new(seed, b)).collect(),
+        }
+    }
+
+    pub fn biomes(&self) -> &[Biome] {
+        &self.biomes
+    }
+
+    pub fn climate(&self, x: i32, z: i32) -> Climate {
+        Climate {
+            temperature: self.temperature.noise_value(x, z),
+            humidity: self.humidity.noise_value(x, z),
+            continentalness: self.continentalness.noise_value(x, z),
+        }
+    }
+
+    pub fn weights(&self, x: i32, z: i32) -> BiomeWeights {
+        let climate = self.climate(x, z);
+        let distances: SmallVec<[f64; 8]> = self
+            .biomes
+            .iter()
+            .map(|b| b.climate.distance(&climate))
+            .collect();
+        let closest = distances.iter().cloned().fold(f64::INFINITY, f64::min);
+
+        let mut weights: BiomeWeights = distances
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (i, self.blend - (d - closest)))
+            .filter(|(_, w)| *w > 0.0)
+            .collect();
+        let total: f64 = weights.iter().map(|(_, w)| w).sum();
+        for (_, w) in weights.iter_mut() {
+            *w /= total;
+        }
+        weights.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        weights
+    }
+
+    /// the biome with the largest influence on the column of the position
+    pub fn biome_at(&self, position: &VoxelPosition) -> &Biome {
+        &self.biomes[self.weights(position.x, position.z)[0].0]
+    }
+
+    pub fn height(&self, weights: &BiomeWeights, x: i32, z: i32) -> i32 {
+        weights
+            .iter()
+            .map(|(i, w)| self.biomes[*i].height.height(x, z) * w)
+            .sum::<f64>() as i32
+    }
+
+    /// temperature in degrees, decreasing with the height
+    pub fn temperature(&self, x: i32, y: i32, z: i32) -> f64 {
+        let mut temperature = self.temperature.sample(x, z);
+        if y > -10 {
+            temperature -= y as f64 / 1.5;
+        }
+        temperature
+    }
+
+    /// Picks the biome deciding the voxel types of a column randomly by the weights,
+    /// which dithers the types at biome borders.
+    pub fn type_biome(&self, rng: &mut SmallRng, weights: &BiomeWeights) -> usize {
+        let mut remaining: f64 = rng.gen();
+        for (i, w) in weights.iter() {
+            remaining -= w;
+            if remaining < 0.0 {
+                return *i;
+            }
+        }
+        weights[0].0
+    }
+
+    pub fn get_type(
+        &self,
+        rng: &mut SmallRng,
+        biome: usize,
+        x: i32,
+        y: i32,
+        z: i32,
+        ground: bool,
+    ) -> VoxelTypes {
+        self.biomes[biome]
+            .types
+            .get_type(rng, self.temperature(x, y, z), y, ground)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        voxel::VoxelPosition,
+        world_gen::{config::WorldGenConfig, seed::WorldSeed},
+    };
+
+    use super::BiomeMap;
+
+    /// two biomes with a flat terrain at different heights that only differ in continentalness
+    fn flat_biomes() -> BiomeMap {
+        let mut config = WorldGenConfig::default();
+        config.climate.continentalness.frequency = 100.0;
+        config.biome_blend = 0.3;
+        config.biomes.truncate(2);
+        for (biome, (height, continentalness)) in config
+            .biomes
+            .iter_mut()
+            .zip(vec![(10.0, -0.5), (50.0, 0.5)])
+        {
+            biome.temperature = 0.0;
+            biome.humidity = 0.0;
+            biome.continentalness = continentalness;
+            biome.height.truncate(1);
+            biome.height[0].multiplier = 0.0;
+            biome.height[0].squared = false;
+            biome.height[0].offset = height;
+        }
+        config.validate().unwrap();
+        BiomeMap::new(&WorldSeed::new(3), &config)
+    }
+
+    #[test]
+    fn weights_are_normalized() {
+        let biomes = flat_biomes();
+        for x in (0..5000).step_by(37) {
+            let weights = biomes.weights(x, 0);
+            let total: f64 = weights.iter().map(|(_, w)| w).sum();
+            assert!((total - 1.0).abs() < 1e-9);
+            assert!(weights.windows(2).all(|w| w[0].1 >= w[1].1));
+        }
+    }
+
+    #[test]
+    fn height_is_blended_across_biome_borders() {
+        let biomes = flat_biomes();
+        let mut names = Vec::new();
+        let mut previous = None;
+        for x in 0..10000 {
+            let weights = biomes.weights(x, 0);
+            let height = biomes.height(&weights, x, 0);
+            assert!((9..=50).contains(&height));
+            if let Some(previous) = previous {
+                let step: i32 = height - previous;
+                assert!(
+                    step.abs() < 10,
+                    "height jumps from {} to {}",
+                    previous,
+                    height
+                );
+            }
+            previous = Some(height);
+
+            let name = &biomes.biome_at(&VoxelPosition::new(x, 0, 0)).name;
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        assert_eq!(names.len(), 2);
+    }
+}
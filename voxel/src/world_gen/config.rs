@@ -0,0 +1,488 @@
+use std::{fmt, fs, io, path::Path};
+
+use serde::Deserialize;
+
+use crate::voxel::VoxelTypes;
+
+pub const WORLD_GEN_CONFIG_PATH: &str = "assets/world_gen.ron";
+
+/// used if the config file does not exist
+const DEFAULT_CONFIG: &str = include_str!("../../../assets/world_gen.ron");
+
+/*
+Everything that shapes the generated terrain: the climate samplers that decide the biomes,
+the height noise layers and voxel type rules of every biome and the structures placed on top of the terrain.
+The seeds of the noise layers are derived from the WorldSeed and the salt of the layer.
+ */
+#[derive(Clone, Debug, Deserialize)]
+pub struct WorldGenConfig {
+    pub climate: ClimateConfig,
+    /// climate distance over which neighbouring biomes are blended into each other
+    pub biome_blend: f64,
+    pub biomes: Vec<BiomeConfig>,
+    pub caves: CaveConfig,
+    pub structures: StructuresConfig,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ClimateConfig {
+    /// also used by the voxel type rules, so its multiplier and offset are in degrees
+    pub temperature: NoiseLayerConfig,
+    pub humidity: NoiseLayerConfig,
+    pub continentalness: NoiseLayerConfig,
+}
+
+/// The climate values of a biome are compared to the raw noise of the climate samplers, so they lie within [-1, 1].
+#[derive(Clone, Debug, Deserialize)]
+pub struct BiomeConfig {
+    pub name: String,
+    pub temperature: f64,
+    pub humidity: f64,
+    pub continentalness: f64,
+    /// summed up to the terrain height within the biome
+    pub height: Vec<NoiseLayerConfig>,
+    pub voxel_types: Vec<VoxelTypeBoundaryConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NoiseLayerConfig {
+    pub salt: u64,
+    /// distance in voxels between the lattice points of the noise
+    pub frequency: f64,
+    pub multiplier: f64,
+    #[serde(default)]
+    pub squared: bool,
+    #[serde(default)]
+    pub offset: f64,
+}
+
+/// A voxel type can be chosen for voxels within all bounds, omitted bounds are unlimited.
+#[derive(Clone, Debug, Deserialize)]
+pub struct VoxelTypeBoundaryConfig {
+    pub typ: VoxelTypes,
+    #[serde(default)]
+    pub min_y: Option<i32>,
+    #[serde(default)]
+    pub max_y: Option<i32>,
+    #[serde(default)]
+    pub min_temperature: Option<f64>,
+    #[serde(default)]
+    pub max_temperature: Option<f64>,
+    /// only used for the topmost voxel of the terrain if true, only below otherwise
+    #[serde(default)]
+    pub only_ground: bool,
+}
+
+/*
+The terrain is solid where the density is positive. The density is the distance below the height of the biomes
+plus the overhang noise, so the surface can fold over itself. Where the cave noise exceeds the threshold
+the terrain is carved out again.
+ */
+#[derive(Clone, Debug, Deserialize)]
+pub struct CaveConfig {
+    /// 3D noise, its multiplier limits how far the surface moves away from the height of the biomes
+    pub overhang: NoiseLayerConfig,
+    /// 3D noise
+    pub noise: NoiseLayerConfig,
+    pub threshold: f64,
+    /// caves are only carved above this y, chunks down to it are generated below the player
+    pub min_y: i32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct StructuresConfig {
+    pub pillars: PillarConfig,
+    pub arches: ArchConfig,
+    pub boulders: BoulderConfig,
+    pub ruins: RuinConfig,
+}
+
+/// The world is split into square regions of region_size voxels, each contains a structure with the given chance.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PlacementConfig {
+    pub region_size: i32,
+    pub chance: f64,
+}
+
+/// Ranges are (inclusive start, exclusive end).
+#[derive(Clone, Debug, Deserialize)]
+pub struct PillarConfig {
+    pub placement: PlacementConfig,
+    pub height: (i32, i32),
+    pub upper_radius: (i32, i32),
+    pub mid_radius: (i32, i32),
+    pub lower_radius: (i32, i32),
+    pub rock_types: Vec<VoxelTypes>,
+}
+
+/// Ranges are (inclusive start, exclusive end).
+#[derive(Clone, Debug, Deserialize)]
+pub struct ArchConfig {
+    pub placement: PlacementConfig,
+    /// distance between the outer sides of the legs
+    pub span: (i32, i32),
+    pub thickness: (i32, i32),
+    pub rock_types: Vec<VoxelTypes>,
+}
+
+/// Ranges are (inclusive start, exclusive end).
+#[derive(Clone, Debug, Deserialize)]
+pub struct BoulderConfig {
+    pub placement: PlacementConfig,
+    pub radius: (i32, i32),
+    pub rock_types: Vec<VoxelTypes>,
+}
+
+/// Ranges are (inclusive start, exclusive end).
+#[derive(Clone, Debug, Deserialize)]
+pub struct RuinConfig {
+    pub placement: PlacementConfig,
+    /// length of the walls
+    pub size: (i32, i32),
+    pub wall_height: (i32, i32),
+    pub rock_types: Vec<VoxelTypes>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(ron::Error),
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "could not read world gen config: {}", e),
+            ConfigError::Parse(e) => write!(f, "could not parse world gen config: {}", e),
+            ConfigError::Invalid(errors) => {
+                write!(f, "invalid world gen config:")?;
+                for error in errors {
+                    write!(f, "\n  - {}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Default for WorldGenConfig {
+    fn default() -> Self {
+        WorldGenConfig::parse(DEFAULT_CONFIG).expect("default world gen config is invalid")
+    }
+}
+
+impl WorldGenConfig {
+    /// falls back to the default config if the file does not exist
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<WorldGenConfig, ConfigError> {
+        match fs::read_to_string(path) {
+            Ok(content) => WorldGenConfig::parse(&content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(WorldGenConfig::default()),
+            Err(e) => Err(ConfigError::Io(e)),
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<WorldGenConfig, ConfigError> {
+        let config: WorldGenConfig = ron::de::from_str(content).map_err(ConfigError::Parse)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// collects all problems instead of stopping at the first one
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+
+        self.climate
+            .temperature
+            .validate("climate.temperature", &mut errors);
+        self.climate
+            .humidity
+            .validate("climate.humidity", &mut errors);
+        self.climate
+            .continentalness
+            .validate("climate.continentalness", &mut errors);
+        if !(self.biome_blend.is_finite() && self.biome_blend > 0.0) {
+            errors.push(format!(
+                "biome_blend must be positive, was {}",
+                self.biome_blend
+            ));
+        }
+
+        if self.biomes.is_empty() {
+            errors.push("biomes needs at least one biome".to_string());
+        }
+        for (i, biome) in self.biomes.iter().enumerate() {
+            biome.validate(&format!("biomes[{}] ({})", i, biome.name), &mut errors);
+            if self.biomes[..i].iter().any(|b| b.name == biome.name) {
+                errors.push(format!(
+                    "biomes[{}]: the name {} is used more than once",
+                    i, biome.name
+                ));
+            }
+        }
+
+        self.caves.validate("caves", &mut errors);
+        self.structures.validate("structures", &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+}
+
+impl BiomeConfig {
+    fn validate(&self, path: &str, errors: &mut Vec<String>) {
+        for (name, value) in [
+            ("temperature", self.temperature),
+            ("humidity", self.humidity),
+            ("continentalness", self.continentalness),
+        ]
+        .iter()
+        {
+            if !(-1.0..=1.0).contains(value) {
+                errors.push(format!(
+                    "{}.{} must be within [-1, 1], was {}",
+                    path, name, value
+                ));
+            }
+        }
+
+        if self.height.is_empty() {
+            errors.push(format!("{}.height needs at least one noise layer", path));
+        }
+        for (i, layer) in self.height.iter().enumerate() {
+            layer.validate(&format!("{}.height[{}]", path, i), errors);
+        }
+
+        for (i, boundary) in self.voxel_types.iter().enumerate() {
+            boundary.validate(&format!("{}.voxel_types[{}]", path, i), errors);
+        }
+        for only_ground in [true, false].iter() {
+            if let Some(y) = self.uncovered_y(*only_ground) {
+                errors.push(format!(
+                    "{}.voxel_types do not cover y {} for {} voxels at every temperature",
+                    path,
+                    y,
+                    if *only_ground { "ground" } else { "non ground" }
+                ));
+            }
+        }
+    }
+
+    /*
+    Every voxel needs at least one valid type. Only boundaries without temperature limits are considered,
+    since the temperature is not bounded. Returns the first y that is not covered.
+     */
+    fn uncovered_y(&self, only_ground: bool) -> Option<i32> {
+        let mut ranges: Vec<(i32, i32)> = self
+            .voxel_types
+            .iter()
+            .filter(|b| {
+                b.only_ground == only_ground
+                    && b.min_temperature.is_none()
+                    && b.max_temperature.is_none()
+            })
+            .map(|b| (b.min_y.unwrap_or(i32::MIN), b.max_y.unwrap_or(i32::MAX)))
+            .collect();
+        ranges.sort_unstable();
+
+        let mut next = i32::MIN;
+        for (min, max) in ranges {
+            if min > next {
+                return Some(next);
+            }
+            if max == i32::MAX {
+                return None;
+            }
+            next = next.max(max + 1);
+        }
+        Some(next)
+    }
+}
+
+impl NoiseLayerConfig {
+    fn validate(&self, path: &str, errors: &mut Vec<String>) {
+        if !(self.frequency.is_finite() && self.frequency > 0.0) {
+            errors.push(format!(
+                "{}.frequency must be positive, was {}",
+                path, self.frequency
+            ));
+        }
+        if !self.multiplier.is_finite() {
+            errors.push(format!("{}.multiplier must be finite", path));
+        }
+        if !self.offset.is_finite() {
+            errors.push(format!("{}.offset must be finite", path));
+        }
+    }
+}
+
+impl VoxelTypeBoundaryConfig {
+    fn validate(&self, path: &str, errors: &mut Vec<String>) {
+        if let (Some(min), Some(max)) = (self.min_y, self.max_y) {
+            if min > max {
+                errors.push(format!(
+                    "{} ({:?}): min_y {} is larger than max_y {}",
+                    path, self.typ, min, max
+                ));
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_temperature, self.max_temperature) {
+            if min > max {
+                errors.push(format!(
+                    "{} ({:?}): min_temperature {} is larger than max_temperature {}",
+                    path, self.typ, min, max
+                ));
+            }
+        }
+    }
+}
+
+impl CaveConfig {
+    fn validate(&self, path: &str, errors: &mut Vec<String>) {
+        self.overhang
+            .validate(&format!("{}.overhang", path), errors);
+        self.noise.validate(&format!("{}.noise", path), errors);
+        if !self.threshold.is_finite() {
+            errors.push(format!("{}.threshold must be finite", path));
+        }
+    }
+}
+
+impl StructuresConfig {
+    fn validate(&self, path: &str, errors: &mut Vec<String>) {
+        let pillars = &self.pillars;
+        let pillars_path = format!("{}.pillars", path);
+        pillars
+            .placement
+            .validate(&format!("{}.placement", pillars_path), errors);
+        validate_range(&pillars_path, "height", pillars.height, 1, errors);
+        validate_range(
+            &pillars_path,
+            "upper_radius",
+            pillars.upper_radius,
+            0,
+            errors,
+        );
+        validate_range(&pillars_path, "mid_radius", pillars.mid_radius, 0, errors);
+        validate_range(
+            &pillars_path,
+            "lower_radius",
+            pillars.lower_radius,
+            0,
+            errors,
+        );
+        validate_rock_types(&pillars_path, &pillars.rock_types, errors);
+
+        let arches = &self.arches;
+        let arches_path = format!("{}.arches", path);
+        arches
+            .placement
+            .validate(&format!("{}.placement", arches_path), errors);
+        validate_range(&arches_path, "span", arches.span, 3, errors);
+        validate_range(&arches_path, "thickness", arches.thickness, 1, errors);
+        validate_rock_types(&arches_path, &arches.rock_types, errors);
+
+        let boulders = &self.boulders;
+        let boulders_path = format!("{}.boulders", path);
+        boulders
+            .placement
+            .validate(&format!("{}.placement", boulders_path), errors);
+        validate_range(&boulders_path, "radius", boulders.radius, 1, errors);
+        validate_rock_types(&boulders_path, &boulders.rock_types, errors);
+
+        let ruins = &self.ruins;
+        let ruins_path = format!("{}.ruins", path);
+        ruins
+            .placement
+            .validate(&format!("{}.placement", ruins_path), errors);
+        validate_range(&ruins_path, "size", ruins.size, 3, errors);
+        validate_range(&ruins_path, "wall_height", ruins.wall_height, 1, errors);
+        validate_rock_types(&ruins_path, &ruins.rock_types, errors);
+    }
+}
+
+impl PlacementConfig {
+    fn validate(&self, path: &str, errors: &mut Vec<String>) {
+        if self.region_size < 1 {
+            errors.push(format!(
+                "{}.region_size must be positive, was {}",
+                path, self.region_size
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.chance) {
+            errors.push(format!(
+                "{}.chance must be within [0, 1], was {}",
+                path, self.chance
+            ));
+        }
+    }
+}
+
+fn validate_range(path: &str, name: &str, range: (i32, i32), min: i32, errors: &mut Vec<String>) {
+    let (start, end) = range;
+    if start >= end || start < min {
+        errors.push(format!(
+            "{}.{} must be a non empty range starting at {} or above, was ({}, {})",
+            path, name, min, start, end
+        ));
+    }
+}
+
+fn validate_rock_types(path: &str, rock_types: &[VoxelTypes], errors: &mut Vec<String>) {
+    if rock_types.is_empty() {
+        errors.push(format!("{}.rock_types must not be empty", path));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigError, WorldGenConfig, DEFAULT_CONFIG};
+
+    #[test]
+    fn default_config_is_valid() {
+        assert!(WorldGenConfig::parse(DEFAULT_CONFIG).is_ok());
+    }
+
+    #[test]
+    fn reports_all_validation_errors() {
+        let mut config = WorldGenConfig::parse(DEFAULT_CONFIG).unwrap();
+        config.biomes[0].height[0].frequency = 0.0;
+        config.structures.pillars.height = (20, 10);
+
+        match config.validate() {
+            Err(ConfigError::Invalid(errors)) => {
+                assert_eq!(errors.len(), 2);
+                assert!(errors[0].starts_with(&format!(
+                    "biomes[0] ({}).height[0].frequency",
+                    config.biomes[0].name
+                )));
+                assert!(errors[1].starts_with("structures.pillars.height"));
+            }
+            other => panic!("expected validation errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn detects_uncovered_heights() {
+        let mut config = WorldGenConfig::parse(DEFAULT_CONFIG).unwrap();
+        config.biomes[0]
+            .voxel_types
+            .retain(|b| b.only_ground || b.min_y.is_none());
+
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn detects_duplicate_biome_names() {
+        let mut config = WorldGenConfig::parse(DEFAULT_CONFIG).unwrap();
+        let duplicate = config.biomes[0].clone();
+        config.biomes.push(duplicate);
+
+        assert!(config.validate().is_err());
+    }
+}
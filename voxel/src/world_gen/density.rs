@@ -0,0 +1,51 @@
+use super::{config::CaveConfig, noise_sampler::NoiseSampler, seed::WorldSeed};
+
+/// 3D density of the terrain, see CaveConfig
+#[derive(Clone)]
+pub struct TerrainDensity {
+    overhang: NoiseSampler,
+    overhang_range: i32,
+    caves: NoiseSampler,
+    threshold: f64,
+    min_y: i32,
+}
+
+impl TerrainDensity {
+    pub fn new(seed: &WorldSeed, config: &CaveConfig) -> TerrainDensity {
+        let overhang = NoiseSampler::new(seed, &config.overhang);
+        TerrainDensity {
+            overhang_range: overhang.max_abs().ceil() as i32,
+            overhang,
+            caves: NoiseSampler::new(seed, &config.noise),
+            threshold: config.threshold,
+            min_y: config.min_y,
+        }
+    }
+
+    /// height is the height of the biomes at the column of the voxel
+    pub fn is_solid(&self, x: i32, y: i32, z: i32, height: i32) -> bool {
+        let depth = height - y;
+        let solid = if depth > self.overhang_range {
+            true
+        } else if depth < -self.overhang_range {
+            false
+        } else {
+            depth as f64 + self.overhang.sample_3d(x, y, z) > 0.0
+        };
+        solid && !(y >= self.min_y && self.caves.sample_3d(x, y, z) > self.threshold)
+    }
+
+    /// all voxels at or above the returned y are air
+    pub fn top(&self, height: i32) -> i32 {
+        height + self.overhang_range + 1
+    }
+
+    /// y of the topmost solid voxel of the column, every column is solid below the caves
+    pub fn surface(&self, x: i32, z: i32, height: i32) -> i32 {
+        let mut y = self.top(height) - 1;
+        while !self.is_solid(x, y, z, height) {
+            y -= 1;
+        }
+        y
+    }
+}
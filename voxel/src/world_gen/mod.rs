@@ -1,43 +1,101 @@
+mod biome;
+mod config;
+mod density;
 mod height;
 mod noise_sampler;
+mod seed;
+mod structures;
 mod type_decision;
+mod unload;
 
+use ahash::AHashSet;
 use bevy::{prelude::*, tasks::AsyncComputeTaskPool};
 use common::PlayerPosition;
 use flume::{unbounded, Receiver, Sender};
-use rand::{prelude::SmallRng, SeedableRng};
 
 use crate::{
     access::VoxelAccess,
-    boundaries::ChunkBoundaries,
+    boundaries::{ChunkBoundaries, CHUNK_SIZE},
     chunk::VoxelChunk,
+    chunk_mesh::ChunkMesher,
     lod::distance_2_lod,
-    voxel::{Voxel, VoxelPosition},
+    model::ChunkRemeshRequests,
+    neighbours::ChunkNeighbours,
+    persistence::ChunkStore,
+    voxel::{Voxel, VoxelDirection, VoxelPosition},
 };
+use strum::IntoEnumIterator;
 
-use self::{height::HeightGen, type_decision::VoxelTypeDecision};
+pub use self::{
+    biome::{Biome, BiomeMap, BiomeWeights, Climate},
+    config::{
+        ArchConfig, BiomeConfig, BoulderConfig, CaveConfig, ClimateConfig, ConfigError,
+        NoiseLayerConfig, PillarConfig, PlacementConfig, RuinConfig, StructuresConfig,
+        VoxelTypeBoundaryConfig, WorldGenConfig, WORLD_GEN_CONFIG_PATH,
+    },
+    density::TerrainDensity,
+    seed::WorldSeed,
+    structures::{
+        ArchGenerator, BoulderGenerator, PillarGenerator, RuinGenerator, StructureBounds,
+        StructureGenerator, Structures,
+    },
+    unload::unload_distant_chunks,
+};
 
-use super::{AdditionalVoxels, VoxelTexture};
+use super::VoxelTexture;
 
 pub struct GeneratedChunks {
-    generated: Vec<ChunkBoundaries>,
+    generated: AHashSet<ChunkBoundaries>,
+}
+
+/// horizontal distances in chunks around the player chunk
+pub struct ChunkLoadingRadius {
+    /// chunks up to this distance are generated or loaded
+    pub load: i32,
+    /// chunks beyond this distance are unloaded, should be larger than load to avoid reloading at the border
+    pub unload: i32,
+    /// chunks down to this y are loaded below the player, so the caves are covered
+    pub lowest_y: i32,
 }
 
-const CHUNKS_IN_EACH_DIRECTION: i32 = 6;
+const CHUNKS_BELOW: i32 = 1;
+const CHUNKS_ABOVE: i32 = 4;
+
+impl ChunkLoadingRadius {
+    /// lowest and highest vertical chunk offset relative to the player chunk that are loaded
+    pub fn vertical(&self, player_chunk: &ChunkBoundaries) -> (i32, i32) {
+        let lowest_chunk = ChunkBoundaries::aligned(VoxelPosition::new(0, self.lowest_y, 0));
+        let lowest = (lowest_chunk.min[1] - player_chunk.min[1]) / CHUNK_SIZE;
+        (lowest.min(-CHUNKS_BELOW), CHUNKS_ABOVE)
+    }
+}
 
 pub struct GenerationResult {
     boundaries: ChunkBoundaries,
     chunk: VoxelChunk,
     mesh: Mesh,
+    neighbours: ChunkNeighbours,
 }
 
-pub fn setup_world_gen(mut commands: Commands) {
+pub fn setup_world_gen(mut commands: Commands, seed: Res<WorldSeed>) {
+    let config = WorldGenConfig::load(WORLD_GEN_CONFIG_PATH).unwrap_or_else(|e| {
+        error!("{}\nFalling back to the default world gen config", e);
+        WorldGenConfig::default()
+    });
+
     let (sender, receiver) = unbounded::<GenerationResult>();
-    commands.insert_resource(VoxelTypeDecision::default());
-    commands.insert_resource(HeightGen::new());
+    commands.insert_resource(BiomeMap::new(&seed, &config));
+    commands.insert_resource(TerrainDensity::new(&seed, &config.caves));
+    commands.insert_resource(Structures::new(&config.structures));
     commands.insert_resource(GeneratedChunks {
-        generated: Vec::new(),
+        generated: AHashSet::new(),
     });
+    commands.insert_resource(ChunkLoadingRadius {
+        load: 6,
+        unload: 8,
+        lowest_y: config.caves.min_y,
+    });
+    commands.insert_resource(config);
     commands.insert_resource(sender);
     commands.insert_resource(receiver);
 }
@@ -47,46 +105,57 @@ pub fn start_generation(
     pool: Res<AsyncComputeTaskPool>,
     mut generated_chunks: ResMut<GeneratedChunks>,
     player_position: Res<PlayerPosition>,
-    additional_voxels: Res<AdditionalVoxels>,
-    voxel_type_decision: Res<VoxelTypeDecision>,
-    height_gen: Res<HeightGen>,
+    structures: Res<Structures>,
+    biome_map: Res<BiomeMap>,
+    density: Res<TerrainDensity>,
+    chunk_store: Res<ChunkStore>,
+    radius: Res<ChunkLoadingRadius>,
+    mesher: Res<ChunkMesher>,
+    chunk_access: Res<VoxelAccess>,
+    seed: Res<WorldSeed>,
 ) {
     let player_chunk =
         ChunkBoundaries::aligned(VoxelPosition::from_vec3(&player_position.position));
 
-    for x in -CHUNKS_IN_EACH_DIRECTION..CHUNKS_IN_EACH_DIRECTION + 1 {
-        for y in -1..4 + 1 {
-            for z in -CHUNKS_IN_EACH_DIRECTION..CHUNKS_IN_EACH_DIRECTION + 1 {
+    let (lowest, highest) = radius.vertical(&player_chunk);
+    for x in -radius.load..radius.load + 1 {
+        for y in lowest..highest + 1 {
+            for z in -radius.load..radius.load + 1 {
                 let boundaries = player_chunk.in_direction([x, y, z]);
-                if let None = generated_chunks
-                    .generated
-                    .iter()
-                    .find(|c| **c == boundaries)
-                {
+                if !generated_chunks.generated.contains(&boundaries) {
                     let cloned_boundary = boundaries.clone();
-                    generated_chunks.generated.push(boundaries);
+                    generated_chunks.generated.insert(boundaries);
                     let cloned_sender = sender.clone();
-                    let additional = additional_voxels
-                        .voxels
-                        .get(&cloned_boundary)
-                        .map(|c| c.clone())
-                        .unwrap_or(vec![]);
-                    let cloned_voxel_type_decision = voxel_type_decision.clone();
-                    let cloned_height_gen = height_gen.clone();
+                    let cloned_biome_map = biome_map.clone();
+                    let cloned_density = density.clone();
+                    let cloned_structures = structures.clone();
+                    let cloned_store = chunk_store.clone();
+                    let mesher = *mesher;
+                    let seed = *seed;
+                    let neighbours = ChunkNeighbours::from_access(&chunk_access, &cloned_boundary);
                     let lod = distance_2_lod(
                         player_position
                             .position
                             .distance(cloned_boundary.center().to_vec()),
                     );
                     pool.spawn(async move {
-                        generate_chunk(
-                            cloned_boundary,
-                            cloned_voxel_type_decision,
-                            cloned_height_gen,
-                            cloned_sender,
-                            additional,
-                            lod,
-                        );
+                        let mut chunk = cloned_store.load(&cloned_boundary).unwrap_or_else(|| {
+                            generate_chunk(
+                                cloned_boundary,
+                                &seed,
+                                &cloned_biome_map,
+                                &cloned_density,
+                                &cloned_structures,
+                            )
+                        });
+                        chunk.lod = lod;
+                        let mesh = mesher.mesh(&chunk, &neighbours);
+                        let _ = cloned_sender.send(GenerationResult {
+                            boundaries: cloned_boundary,
+                            chunk,
+                            mesh,
+                            neighbours,
+                        });
                     })
                     .detach()
                 }
@@ -97,41 +166,41 @@ pub fn start_generation(
 
 fn generate_chunk(
     boundaries: ChunkBoundaries,
-    voxel_type_decision: VoxelTypeDecision,
-    height_gen: HeightGen,
-    sender: Sender<GenerationResult>,
-    additional: Vec<Voxel>,
-    lod: i32,
-) {
-    let mut rng = SmallRng::from_entropy();
+    seed: &WorldSeed,
+    biome_map: &BiomeMap,
+    density: &TerrainDensity,
+    structures: &Structures,
+) -> VoxelChunk {
+    let mut rng = seed.chunk_rng(&boundaries);
     let mut chunk = VoxelChunk::empty(boundaries.clone());
-    chunk.lod = lod;
     for x_i in boundaries.min[0]..boundaries.max[0] {
         for z_i in boundaries.min[2]..boundaries.max[2] {
-            let total_y = height_gen.get_height_factor(x_i, z_i);
-            let max_y = boundaries.max[1].min(total_y);
-            if boundaries.min[1] <= max_y {
-                for y in boundaries.min[1]..max_y {
-                    let p = VoxelPosition { x: x_i, y, z: z_i };
+            let weights = biome_map.weights(x_i, z_i);
+            let height = biome_map.height(&weights, x_i, z_i);
+            let top = density.top(height);
+            if boundaries.min[1] >= top {
+                continue;
+            }
+            let biome = biome_map.type_biome(&mut rng, &weights);
+            // walk down the column, so the topmost voxel of each solid section is known as ground
+            let mut above_solid =
+                boundaries.max[1] < top && density.is_solid(x_i, boundaries.max[1], z_i, height);
+            for y in (boundaries.min[1]..boundaries.max[1].min(top)).rev() {
+                let solid = density.is_solid(x_i, y, z_i, height);
+                if solid {
                     chunk.set(Voxel {
-                        position: p,
-                        typ: voxel_type_decision.get_type(&mut rng, x_i, y, z_i, y >= total_y - 1),
+                        position: VoxelPosition { x: x_i, y, z: z_i },
+                        typ: biome_map.get_type(&mut rng, biome, x_i, y, z_i, !above_solid),
                     });
                 }
+                above_solid = solid;
             }
         }
     }
-    for v in additional {
+    for v in structures.voxels_in(seed, biome_map, density, &boundaries) {
         chunk.set(v);
     }
-    let mesh = Mesh::from(&chunk);
-
-    let result = GenerationResult {
-        boundaries,
-        chunk,
-        mesh,
-    };
-    let _ = sender.send(result);
+    chunk
 }
 
 pub fn read_generation_results(
@@ -140,17 +209,55 @@ pub fn read_generation_results(
     mut meshes: ResMut<Assets<Mesh>>,
     mut chunk_access: ResMut<VoxelAccess>,
     material: Res<VoxelTexture>,
+    mesher: Res<ChunkMesher>,
+    generated_chunks: Res<GeneratedChunks>,
+    mut remesh_requests: ResMut<ChunkRemeshRequests>,
+    mesh_query: Query<&Handle<Mesh>>,
 ) {
+    // chunks spawned with a mesh this frame, their components are not queryable yet
+    let mut meshed_this_frame = AHashSet::new();
     for generation in receiver.try_iter() {
+        // the chunk was unloaded while it was generated
+        if !generated_chunks.generated.contains(&generation.boundaries) {
+            continue;
+        }
+        let mut replaced_voxels = false;
+        if let Some((previous_entity, previous)) = chunk_access.remove_chunk(&generation.boundaries)
+        {
+            if let Ok(mesh) = mesh_query.get(previous_entity) {
+                meshes.remove(mesh);
+            }
+            commands.entity(previous_entity).despawn();
+            replaced_voxels = previous.count > 0;
+        }
+        // an empty chunk only changes the faces of its neighbours if it replaced voxels
+        if generation.chunk.count > 0 || replaced_voxels {
+            for direction in VoxelDirection::iter() {
+                let neighbour = generation.boundaries.in_direction(direction.offset());
+                if let Some((neighbour_entity, neighbour_chunk)) =
+                    chunk_access.get_chunk_entity(&neighbour)
+                {
+                    // only a neighbour with a mesh has faces towards this chunk,
+                    // removed voxels can uncover faces of a neighbour without one
+                    if mesh_query.get(*neighbour_entity).is_ok()
+                        || meshed_this_frame.contains(&neighbour)
+                        || (replaced_voxels && neighbour_chunk.count > 0)
+                    {
+                        remesh_requests.boundaries.insert(neighbour);
+                    }
+                    if generation.chunk.count > 0 && !generation.neighbours.has(direction) {
+                        // the neighbour was generated after the mesh of this chunk was built
+                        remesh_requests.boundaries.insert(generation.boundaries);
+                    }
+                }
+            }
+        }
         if generation.chunk.count > 0 {
+            meshed_this_frame.insert(generation.boundaries);
             let chunk_mesh = meshes.add(generation.mesh);
-            let chunk_bundle = PbrBundle {
-                mesh: chunk_mesh,
-                material: material.material.clone(),
-                transform: Transform::from_translation(Vec3::ZERO),
-                ..Default::default()
-            };
-            let chunk_entity = commands.spawn_bundle(chunk_bundle).id();
+            let mut chunk_entity = commands.spawn();
+            material.insert_chunk_mesh(&mut chunk_entity, &mesher, chunk_mesh);
+            let chunk_entity = chunk_entity.id();
             chunk_access.add_chunk(generation.boundaries, chunk_entity, generation.chunk);
         } else {
             let chunk_entity = commands.spawn().id();
@@ -158,3 +265,64 @@ pub fn read_generation_results(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use itertools::iproduct;
+
+    use crate::{
+        boundaries::{ChunkBoundaries, CHUNK_SIZE},
+        chunk::VoxelChunk,
+        persistence::encode_chunk,
+        storage::VOXELS_PER_CHUNK,
+        voxel::VoxelPosition,
+    };
+
+    use super::{generate_chunk, BiomeMap, Structures, TerrainDensity, WorldGenConfig, WorldSeed};
+
+    fn generate(seed: WorldSeed, boundaries: ChunkBoundaries) -> VoxelChunk {
+        let config = WorldGenConfig::default();
+        let biome_map = BiomeMap::new(&seed, &config);
+        let density = TerrainDensity::new(&seed, &config.caves);
+        let structures = Structures::new(&config.structures);
+        generate_chunk(boundaries, &seed, &biome_map, &density, &structures)
+    }
+
+    #[test]
+    fn same_seed_generates_identical_chunks() {
+        let underground = ChunkBoundaries::aligned(VoxelPosition::new(10, -150, -70));
+        let surface = ChunkBoundaries::aligned(VoxelPosition::new(0, 0, 0));
+        for boundaries in [underground, surface].iter() {
+            let first = generate(WorldSeed::new(42), *boundaries);
+            let second = generate(WorldSeed::new(42), *boundaries);
+
+            assert_eq!(first.count, second.count);
+            assert_eq!(encode_chunk(&first), encode_chunk(&second));
+        }
+    }
+
+    #[test]
+    fn different_seeds_generate_different_chunks() {
+        let underground = ChunkBoundaries::aligned(VoxelPosition::new(10, -150, -70));
+        let first = generate(WorldSeed::new(1), underground);
+        let second = generate(WorldSeed::new(2), underground);
+
+        assert_ne!(encode_chunk(&first), encode_chunk(&second));
+    }
+
+    #[test]
+    fn caves_are_carved_below_the_surface() {
+        let boundaries = ChunkBoundaries::aligned(VoxelPosition::new(0, -100, 0));
+        let chunk = generate(WorldSeed::new(7), boundaries);
+
+        assert!(chunk.count > 0);
+        assert!(chunk.count < VOXELS_PER_CHUNK);
+        let has_ceiling =
+            iproduct!(0..CHUNK_SIZE, 0..CHUNK_SIZE - 1, 0..CHUNK_SIZE).any(|(x, y, z)| {
+                let below = VoxelPosition::new(x, boundaries.min[1] + y, z);
+                let above = VoxelPosition::new(x, boundaries.min[1] + y + 1, z);
+                chunk.get(&below).is_none() && chunk.get(&above).is_some()
+            });
+        assert!(has_ceiling);
+    }
+}
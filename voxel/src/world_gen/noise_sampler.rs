@@ -1,4 +1,6 @@
-use noise::{NoiseFn, Perlin};
+use noise::{NoiseFn, Perlin, Seedable};
+
+use super::{config::NoiseLayerConfig, seed::WorldSeed};
 
 #[derive(Clone, Debug)]
 pub struct NoiseSampler {
@@ -10,14 +12,44 @@ pub struct NoiseSampler {
 }
 
 impl NoiseSampler {
+    pub fn new(seed: &WorldSeed, layer: &NoiseLayerConfig) -> NoiseSampler {
+        NoiseSampler {
+            noise: Perlin::new().set_seed(seed.noise_seed(layer.salt)),
+            freqency: layer.frequency,
+            multiplier: layer.multiplier,
+            squared: layer.squared,
+            offset: layer.offset,
+        }
+    }
+
     pub fn sample(&self, x: i32, z: i32) -> f64 {
-        let v = self
-            .noise
-            .get([x as f64 / self.freqency, z as f64 / self.freqency]);
+        self.shape(self.noise_value(x, z))
+    }
+
+    pub fn sample_3d(&self, x: i32, y: i32, z: i32) -> f64 {
+        self.shape(self.noise.get([
+            x as f64 / self.freqency,
+            y as f64 / self.freqency,
+            z as f64 / self.freqency,
+        ]))
+    }
+
+    /// upper bound of the absolute value of the samples
+    pub fn max_abs(&self) -> f64 {
+        self.shape(-1.0).abs().max(self.shape(1.0).abs())
+    }
+
+    fn shape(&self, v: f64) -> f64 {
         if self.squared {
             ((v + 0.2).powi(5)) * self.multiplier + self.offset
         } else {
             v * self.multiplier + self.offset
         }
     }
+
+    /// the noise without multiplier and offset, within [-1, 1]
+    pub fn noise_value(&self, x: i32, z: i32) -> f64 {
+        self.noise
+            .get([x as f64 / self.freqency, z as f64 / self.freqency])
+    }
 }
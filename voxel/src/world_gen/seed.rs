@@ -0,0 +1,58 @@
+use rand::{prelude::SmallRng, SeedableRng};
+
+use crate::boundaries::ChunkBoundaries;
+
+/*
+Seed of the world. Every noise sampler and random number generator used during world generation derives its
+seed from it, so the same seed always produces the same world.
+Chunks get their own generator seeded from the world seed and their boundaries, which keeps the result
+independent of the order in which chunks are generated.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorldSeed {
+    pub seed: u64,
+}
+
+impl Default for WorldSeed {
+    fn default() -> Self {
+        WorldSeed { seed: 0 }
+    }
+}
+
+impl WorldSeed {
+    pub fn new(seed: u64) -> WorldSeed {
+        WorldSeed { seed }
+    }
+
+    /// seed of a noise function, salt distinguishes the different noise functions
+    pub fn noise_seed(&self, salt: u64) -> u32 {
+        (mix(self.seed, salt) >> 32) as u32
+    }
+
+    /// generator for everything that is not bound to a single chunk
+    pub fn rng(&self, salt: u64) -> SmallRng {
+        SmallRng::seed_from_u64(mix(self.seed, salt))
+    }
+
+    /// generator of a region of the world, salt distinguishes the different uses of the same region
+    pub fn region_rng(&self, salt: u64, x: i32, z: i32) -> SmallRng {
+        let hash = mix(mix(mix(self.seed, salt), x as u32 as u64), z as u32 as u64);
+        SmallRng::seed_from_u64(hash)
+    }
+
+    pub fn chunk_rng(&self, boundaries: &ChunkBoundaries) -> SmallRng {
+        let hash = boundaries
+            .min
+            .iter()
+            .fold(self.seed, |hash, v| mix(hash, *v as u32 as u64));
+        SmallRng::seed_from_u64(hash)
+    }
+}
+
+/// splitmix64 finalizer applied to the combination of both values, stable across runs and platforms
+fn mix(a: u64, b: u64) -> u64 {
+    let mut z = a ^ b.wrapping_add(0x9e37_79b9_7f4a_7c15).wrapping_add(a << 6);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,69 @@
+use rand::prelude::*;
+
+use crate::{
+    voxel::{Voxel, VoxelPosition, VoxelTypes},
+    world_gen::config::{ArchConfig, PlacementConfig},
+};
+
+use super::{StructureBounds, StructureGenerator};
+
+/// the legs of an arch reach this far below the terrain height
+const FOUNDATION: i32 = 3;
+
+/// Half ring standing on the terrain, spanning along the x or z axis.
+pub struct ArchGenerator {
+    config: ArchConfig,
+}
+
+impl ArchGenerator {
+    pub fn new(config: ArchConfig) -> ArchGenerator {
+        ArchGenerator { config }
+    }
+}
+
+impl StructureGenerator for ArchGenerator {
+    fn salt(&self) -> u64 {
+        2
+    }
+
+    fn placement(&self) -> &PlacementConfig {
+        &self.config.placement
+    }
+
+    fn bounds(&self) -> StructureBounds {
+        StructureBounds {
+            horizontal: self.config.span.1 / 2 + self.config.thickness.1,
+            below: FOUNDATION,
+            above: self.config.span.1 / 2,
+        }
+    }
+
+    fn generate(&self, rng: &mut SmallRng, anchor: VoxelPosition) -> Vec<Voxel> {
+        let radius = rng.gen_range(self.config.span.0..self.config.span.1) / 2;
+        let thickness = rng.gen_range(self.config.thickness.0..self.config.thickness.1);
+        let along_x: bool = rng.gen();
+        let inner_sq = (radius - thickness).max(0).pow(2);
+        let outer_sq = radius * radius;
+
+        let mut voxels = Vec::new();
+        for a in -radius..=radius {
+            for h in -FOUNDATION..=radius {
+                // the legs continue straight down into the ground
+                let distance_sq = a * a + h.max(0) * h.max(0);
+                if distance_sq < inner_sq || distance_sq > outer_sq {
+                    continue;
+                }
+                for b in -(thickness / 2)..=(thickness / 2) {
+                    let (x, z) = if along_x { (a, b) } else { (b, a) };
+                    let typ = if h == radius {
+                        VoxelTypes::Moss
+                    } else {
+                        self.config.rock_types.choose(rng).unwrap().clone()
+                    };
+                    voxels.push(Voxel::new(anchor.x + x, anchor.y + h, anchor.z + z, typ));
+                }
+            }
+        }
+        voxels
+    }
+}
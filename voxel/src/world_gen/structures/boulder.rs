@@ -0,0 +1,61 @@
+use rand::prelude::*;
+
+use bevy::prelude::Vec3;
+
+use crate::{
+    shapes::{Ellipsoid, VoxelShape},
+    voxel::{Voxel, VoxelPosition},
+    world_gen::config::{BoulderConfig, PlacementConfig},
+};
+
+use super::{StructureBounds, StructureGenerator};
+
+/// Flattened ellipsoid partially sunk into the terrain.
+pub struct BoulderGenerator {
+    config: BoulderConfig,
+}
+
+impl BoulderGenerator {
+    pub fn new(config: BoulderConfig) -> BoulderGenerator {
+        BoulderGenerator { config }
+    }
+}
+
+impl StructureGenerator for BoulderGenerator {
+    fn salt(&self) -> u64 {
+        3
+    }
+
+    fn placement(&self) -> &PlacementConfig {
+        &self.config.placement
+    }
+
+    fn bounds(&self) -> StructureBounds {
+        StructureBounds {
+            horizontal: self.config.radius.1,
+            below: self.config.radius.1,
+            above: 2 * self.config.radius.1,
+        }
+    }
+
+    fn generate(&self, rng: &mut SmallRng, anchor: VoxelPosition) -> Vec<Voxel> {
+        let radius_x = rng.gen_range(self.config.radius.0..self.config.radius.1);
+        let radius_z = rng.gen_range(self.config.radius.0..self.config.radius.1);
+        let radius_y = rng.gen_range(1..=radius_x.min(radius_z));
+        // between a third and two thirds of the boulder are below the terrain
+        let sunk = rng.gen_range(radius_y / 3..=radius_y * 2 / 3);
+        let typ = self.config.rock_types.choose(rng).unwrap().clone();
+
+        Ellipsoid {
+            center: anchor.to_vec() + Vec3::new(0.0, (radius_y - sunk) as f32, 0.0),
+            radii: Vec3::new(radius_x as f32, radius_y as f32, radius_z as f32),
+        }
+        .voxels()
+        .into_iter()
+        .map(|position| Voxel {
+            position,
+            typ: typ.clone(),
+        })
+        .collect()
+    }
+}
@@ -0,0 +1,263 @@
+mod arch;
+mod boulder;
+mod pillar;
+mod ruin;
+
+use std::sync::Arc;
+
+use rand::{prelude::SmallRng, Rng};
+
+use crate::{
+    boundaries::ChunkBoundaries,
+    voxel::{Voxel, VoxelPosition},
+};
+
+use super::{
+    biome::BiomeMap,
+    config::{PlacementConfig, StructuresConfig},
+    density::TerrainDensity,
+    seed::WorldSeed,
+};
+
+pub use self::{
+    arch::ArchGenerator, boulder::BoulderGenerator, pillar::PillarGenerator, ruin::RuinGenerator,
+};
+
+/// voxels of a structure relative to its anchor stay within these bounds
+#[derive(Clone, Copy, Debug)]
+pub struct StructureBounds {
+    /// in x and z direction
+    pub horizontal: i32,
+    pub below: i32,
+    pub above: i32,
+}
+
+/*
+A kind of structure placed on the terrain. The world is split into square regions, for every region the generator
+is asked at most once for a structure, with a random generator derived from the world seed and the region.
+Chunks only keep the voxels of a structure within their boundaries, since every chunk the structure reaches
+generates it the same way, structures can span multiple chunks.
+ */
+pub trait StructureGenerator: Send + Sync {
+    /// distinguishes the placements of the generators, has to be unique among all generators
+    fn salt(&self) -> u64;
+
+    fn placement(&self) -> &PlacementConfig;
+
+    fn bounds(&self) -> StructureBounds;
+
+    /// voxels of a structure standing on the terrain at anchor
+    fn generate(&self, rng: &mut SmallRng, anchor: VoxelPosition) -> Vec<Voxel>;
+}
+
+#[derive(Clone)]
+pub struct Structures {
+    generators: Vec<Arc<dyn StructureGenerator>>,
+}
+
+impl Structures {
+    pub fn new(config: &StructuresConfig) -> Structures {
+        Structures::with_generators(vec![
+            Arc::new(PillarGenerator::new(config.pillars.clone())),
+            Arc::new(ArchGenerator::new(config.arches.clone())),
+            Arc::new(BoulderGenerator::new(config.boulders.clone())),
+            Arc::new(RuinGenerator::new(config.ruins.clone())),
+        ])
+    }
+
+    pub fn with_generators(generators: Vec<Arc<dyn StructureGenerator>>) -> Structures {
+        Structures { generators }
+    }
+
+    /// Decides whether the region contains a structure of the generator and where its anchor is,
+    /// the anchor is the topmost solid voxel of its column.
+    /// Also returns the random generator to create the structure with.
+    pub fn placement(
+        seed: &WorldSeed,
+        biome_map: &BiomeMap,
+        density: &TerrainDensity,
+        generator: &dyn StructureGenerator,
+        region_x: i32,
+        region_z: i32,
+    ) -> Option<(VoxelPosition, SmallRng)> {
+        let placement = generator.placement();
+        let mut rng = seed.region_rng(generator.salt(), region_x, region_z);
+        if rng.gen::<f64>() >= placement.chance {
+            return None;
+        }
+        let x = region_x * placement.region_size + rng.gen_range(0..placement.region_size);
+        let z = region_z * placement.region_size + rng.gen_range(0..placement.region_size);
+        let height = biome_map.height(&biome_map.weights(x, z), x, z);
+        let y = density.surface(x, z, height);
+        Some((VoxelPosition::new(x, y, z), rng))
+    }
+
+    /// voxels of all structures within the boundaries
+    pub fn voxels_in(
+        &self,
+        seed: &WorldSeed,
+        biome_map: &BiomeMap,
+        density: &TerrainDensity,
+        boundaries: &ChunkBoundaries,
+    ) -> Vec<Voxel> {
+        let mut voxels = Vec::new();
+        for generator in self.generators.iter() {
+            let size = generator.placement().region_size;
+            let bounds = generator.bounds();
+            let region = |v: i32| v.div_euclid(size);
+            for region_x in region(boundaries.min[0] - bounds.horizontal)
+                ..=region(boundaries.max[0] - 1 + bounds.horizontal)
+            {
+                for region_z in region(boundaries.min[2] - bounds.horizontal)
+                    ..=region(boundaries.max[2] - 1 + bounds.horizontal)
+                {
+                    if let Some((anchor, mut rng)) = Structures::placement(
+                        seed,
+                        biome_map,
+                        density,
+                        generator.as_ref(),
+                        region_x,
+                        region_z,
+                    ) {
+                        if reaches(boundaries, &anchor, &bounds) {
+                            voxels.extend(
+                                generator
+                                    .generate(&mut rng, anchor)
+                                    .into_iter()
+                                    .filter(|v| boundaries.contains(&v.position)),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        voxels
+    }
+}
+
+fn reaches(boundaries: &ChunkBoundaries, anchor: &VoxelPosition, bounds: &StructureBounds) -> bool {
+    anchor.x - bounds.horizontal < boundaries.max[0]
+        && anchor.x + bounds.horizontal >= boundaries.min[0]
+        && anchor.z - bounds.horizontal < boundaries.max[2]
+        && anchor.z + bounds.horizontal >= boundaries.min[2]
+        && anchor.y - bounds.below < boundaries.max[1]
+        && anchor.y + bounds.above >= boundaries.min[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{prelude::SmallRng, SeedableRng};
+
+    use crate::{
+        boundaries::ChunkBoundaries,
+        voxel::{Voxel, VoxelPosition, VoxelTypes},
+        world_gen::{
+            biome::BiomeMap, config::PlacementConfig, density::TerrainDensity, seed::WorldSeed,
+            WorldGenConfig,
+        },
+    };
+
+    use super::{StructureBounds, StructureGenerator, Structures};
+
+    /// a straight line along x that is longer than a chunk
+    struct Wall {
+        placement: PlacementConfig,
+    }
+
+    impl StructureGenerator for Wall {
+        fn salt(&self) -> u64 {
+            99
+        }
+
+        fn placement(&self) -> &PlacementConfig {
+            &self.placement
+        }
+
+        fn bounds(&self) -> StructureBounds {
+            StructureBounds {
+                horizontal: 100,
+                below: 0,
+                above: 1,
+            }
+        }
+
+        fn generate(&self, _rng: &mut SmallRng, anchor: VoxelPosition) -> Vec<Voxel> {
+            (0..100)
+                .map(|x| Voxel::new(anchor.x + x, anchor.y, anchor.z, VoxelTypes::Snow))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn structures_span_chunk_borders() {
+        let seed = WorldSeed::new(5);
+        let config = WorldGenConfig::default();
+        let biome_map = BiomeMap::new(&seed, &config);
+        let density = TerrainDensity::new(&seed, &config.caves);
+        let wall = Wall {
+            placement: PlacementConfig {
+                region_size: 128,
+                chance: 1.0,
+            },
+        };
+        let (anchor, _) = Structures::placement(&seed, &biome_map, &density, &wall, 0, 0).unwrap();
+        let structures = Structures::with_generators(vec![Arc::new(wall)]);
+
+        for x in 0..100 {
+            let position = VoxelPosition::new(anchor.x + x, anchor.y, anchor.z);
+            let voxels = structures.voxels_in(
+                &seed,
+                &biome_map,
+                &density,
+                &ChunkBoundaries::aligned(position),
+            );
+            assert!(voxels.iter().any(|v| v.position == position));
+        }
+    }
+
+    #[test]
+    fn structures_are_anchored_on_the_topmost_solid_voxel() {
+        let config = WorldGenConfig::default();
+        let wall = Wall {
+            placement: PlacementConfig {
+                region_size: 128,
+                chance: 1.0,
+            },
+        };
+        for seed in 0..20 {
+            let seed = WorldSeed::new(seed);
+            let biome_map = BiomeMap::new(&seed, &config);
+            let density = TerrainDensity::new(&seed, &config.caves);
+            let (anchor, _) =
+                Structures::placement(&seed, &biome_map, &density, &wall, 0, 0).unwrap();
+            let height =
+                biome_map.height(&biome_map.weights(anchor.x, anchor.z), anchor.x, anchor.z);
+
+            assert!(density.is_solid(anchor.x, anchor.y, anchor.z, height));
+            assert!((anchor.y + 1..density.top(height))
+                .all(|y| !density.is_solid(anchor.x, y, anchor.z, height)));
+        }
+    }
+
+    #[test]
+    fn structures_stay_within_their_bounds() {
+        let config = WorldGenConfig::default();
+        let structures = Structures::new(&config.structures);
+        let anchor = VoxelPosition::new(10, 20, -30);
+        for generator in structures.generators.iter() {
+            let bounds = generator.bounds();
+            for seed in 0..20 {
+                let voxels = generator.generate(&mut SmallRng::seed_from_u64(seed), anchor);
+                assert!(!voxels.is_empty());
+                assert!(voxels.iter().all(|v| {
+                    (v.position.x - anchor.x).abs() <= bounds.horizontal
+                        && (v.position.z - anchor.z).abs() <= bounds.horizontal
+                        && v.position.y >= anchor.y - bounds.below
+                        && v.position.y <= anchor.y + bounds.above
+                }));
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use lerp::Lerp;
+use rand::prelude::*;
+
+use crate::{
+    voxel::{Voxel, VoxelPosition, VoxelTypes},
+    world_gen::config::{PillarConfig, PlacementConfig},
+};
+
+use super::{StructureBounds, StructureGenerator};
+
+/// layers of a pillar below the terrain height, so it does not float on slopes
+const FOUNDATION: i32 = 3;
+
+pub struct PillarGenerator {
+    config: PillarConfig,
+}
+
+impl PillarGenerator {
+    pub fn new(config: PillarConfig) -> PillarGenerator {
+        PillarGenerator { config }
+    }
+}
+
+impl StructureGenerator for PillarGenerator {
+    fn salt(&self) -> u64 {
+        1
+    }
+
+    fn placement(&self) -> &PlacementConfig {
+        &self.config.placement
+    }
+
+    fn bounds(&self) -> StructureBounds {
+        StructureBounds {
+            horizontal: self
+                .config
+                .upper_radius
+                .1
+                .max(self.config.mid_radius.1)
+                .max(self.config.lower_radius.1),
+            below: FOUNDATION,
+            above: self.config.height.1,
+        }
+    }
+
+    fn generate(&self, rng: &mut SmallRng, anchor: VoxelPosition) -> Vec<Voxel> {
+        Pillar::new(rng, &self.config, anchor).voxels(rng)
+    }
+}
+
+struct Pillar {
+    position: VoxelPosition,
+    height: i32,
+    upper_radius: i32,
+    mid_radius: i32,
+    lower_radius: i32,
+    rock_types: Vec<VoxelTypes>,
+}
+
+impl Pillar {
+    fn new(rng: &mut SmallRng, config: &PillarConfig, position: VoxelPosition) -> Pillar {
+        Pillar {
+            position,
+            height: rng.gen_range(config.height.0..config.height.1),
+            upper_radius: rng.gen_range(config.upper_radius.0..config.upper_radius.1),
+            mid_radius: rng.gen_range(config.mid_radius.0..config.mid_radius.1),
+            lower_radius: rng.gen_range(config.lower_radius.0..config.lower_radius.1),
+            rock_types: config.rock_types.clone(),
+        }
+    }
+
+    fn voxels(&self, mut rng: &mut SmallRng) -> Vec<Voxel> {
+        let mut world = Vec::new();
+        for layer in -FOUNDATION..self.height {
+            let radius = self.radius_at_level(layer.max(0));
+            let radius_sq = radius * radius;
+            for x in (self.position.x - radius)..(self.position.x + radius) {
+                for z in (self.position.z - radius)..(self.position.z + radius) {
+                    let distance_sq = (self.position.x - x) * (self.position.x - x)
+                        + (self.position.z - z) * (self.position.z - z);
+                    if distance_sq <= radius_sq {
+                        let voxel = Voxel::new(
+                            x,
+                            self.position.y + layer,
+                            z,
+                            self.voxel_type(&mut rng, layer),
+                        );
+                        world.push(voxel);
+                    }
+                }
+            }
+        }
+        world
+    }
+
+    fn voxel_type(&self, mut rng: &mut SmallRng, y: i32) -> VoxelTypes {
+        if y == self.height - 1 {
+            VoxelTypes::Moss
+        } else if y <= 0 {
+            VoxelTypes::GroundRock1
+        } else {
+            self.rock_types.choose(&mut rng).unwrap().clone()
+        }
+    }
+
+    fn radius_at_level(&self, level: i32) -> i32 {
+        if level < 0 || level > self.height {
+            0
+        } else if level == 0 {
+            self.lower_radius
+        } else if level == self.height {
+            self.upper_radius
+        } else if level < self.height / 2 {
+            (self.lower_radius as f32).lerp(
+                self.mid_radius as f32,
+                level as f32 / (self.height as f32 / 2.0),
+            ) as i32
+        } else {
+            (self.mid_radius as f32).lerp(
+                self.upper_radius as f32,
+                (level as f32 - self.height as f32 / 2.0f32) / (self.height as f32 / 2.0),
+            ) as i32
+        }
+    }
+}
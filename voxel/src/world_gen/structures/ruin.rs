@@ -0,0 +1,65 @@
+use rand::prelude::*;
+
+use crate::{
+    voxel::{Voxel, VoxelPosition},
+    world_gen::config::{PlacementConfig, RuinConfig},
+};
+
+use super::{StructureBounds, StructureGenerator};
+
+/// Decayed square walls around a floor, some columns of the walls are missing entirely.
+pub struct RuinGenerator {
+    config: RuinConfig,
+}
+
+impl RuinGenerator {
+    pub fn new(config: RuinConfig) -> RuinGenerator {
+        RuinGenerator { config }
+    }
+}
+
+impl StructureGenerator for RuinGenerator {
+    fn salt(&self) -> u64 {
+        4
+    }
+
+    fn placement(&self) -> &PlacementConfig {
+        &self.config.placement
+    }
+
+    fn bounds(&self) -> StructureBounds {
+        StructureBounds {
+            horizontal: self.config.size.1 / 2,
+            below: 0,
+            above: self.config.wall_height.1,
+        }
+    }
+
+    fn generate(&self, rng: &mut SmallRng, anchor: VoxelPosition) -> Vec<Voxel> {
+        let half = rng.gen_range(self.config.size.0..self.config.size.1) / 2;
+        let wall_height = rng.gen_range(self.config.wall_height.0..self.config.wall_height.1);
+
+        let mut voxels = Vec::new();
+        for x in -half..=half {
+            for z in -half..=half {
+                let floor = self.config.rock_types.choose(rng).unwrap().clone();
+                voxels.push(Voxel::new(anchor.x + x, anchor.y, anchor.z + z, floor));
+
+                if x.abs() != half && z.abs() != half {
+                    continue;
+                }
+                // a quarter of the wall columns are collapsed down to the floor
+                let height = if rng.gen_bool(0.25) {
+                    0
+                } else {
+                    rng.gen_range(1..=wall_height)
+                };
+                for y in 1..=height {
+                    let typ = self.config.rock_types.choose(rng).unwrap().clone();
+                    voxels.push(Voxel::new(anchor.x + x, anchor.y + y, anchor.z + z, typ));
+                }
+            }
+        }
+        voxels
+    }
+}
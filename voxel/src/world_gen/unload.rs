@@ -0,0 +1,91 @@
+use ahash::AHashSet;
+use bevy::prelude::*;
+use common::PlayerPosition;
+
+use crate::{
+    access::VoxelAccess,
+    boundaries::{ChunkBoundaries, CHUNK_SIZE},
+    persistence::ChunkStore,
+    voxel::VoxelPosition,
+};
+
+use super::{ChunkLoadingRadius, GeneratedChunks};
+
+pub fn unload_distant_chunks(
+    mut commands: Commands,
+    mut chunk_access: ResMut<VoxelAccess>,
+    mut generated_chunks: ResMut<GeneratedChunks>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    chunk_store: Res<ChunkStore>,
+    radius: Res<ChunkLoadingRadius>,
+    player_position: Res<PlayerPosition>,
+    mesh_query: Query<&Handle<Mesh>>,
+    mut failed_saves: Local<AHashSet<ChunkBoundaries>>,
+) {
+    let player_chunk =
+        ChunkBoundaries::aligned(VoxelPosition::from_vec3(&player_position.position));
+
+    let distant: Vec<ChunkBoundaries> = generated_chunks
+        .generated
+        .iter()
+        .filter(|b| is_outside(&player_chunk, b, &radius))
+        .cloned()
+        .collect();
+    if distant.is_empty() {
+        return;
+    }
+
+    /*
+    Modified chunks are only unloaded once they are saved. If saving fails they stay loaded and are left
+    to save_modified_chunks, which retries on its timer instead of every frame. Once it succeeded they are
+    clean and get unloaded like any other chunk.
+     */
+    let modified: Vec<_> = distant
+        .iter()
+        .filter(|b| !failed_saves.contains(*b))
+        .filter_map(|b| chunk_access.get_chunk(b))
+        .filter(|chunk| chunk.dirty)
+        .collect();
+    if !modified.is_empty() {
+        if let Err(e) = chunk_store.save(&modified) {
+            warn!("Could not save unloaded chunks, keeping them loaded: {}", e);
+            failed_saves.extend(modified.iter().map(|chunk| chunk.boundary));
+        } else {
+            let saved: Vec<ChunkBoundaries> = modified.iter().map(|chunk| chunk.boundary).collect();
+            for boundaries in saved {
+                if let Some(chunk) = chunk_access.get_chunk_mut(&boundaries) {
+                    chunk.dirty = false;
+                }
+            }
+        }
+    }
+
+    for boundaries in distant {
+        if chunk_access
+            .get_chunk(&boundaries)
+            .map_or(false, |chunk| chunk.dirty)
+        {
+            continue;
+        }
+        failed_saves.remove(&boundaries);
+        if let Some((entity, _)) = chunk_access.remove_chunk(&boundaries) {
+            if let Ok(mesh) = mesh_query.get(entity) {
+                meshes.remove(mesh);
+            }
+            commands.entity(entity).despawn();
+        }
+        generated_chunks.generated.remove(&boundaries);
+    }
+}
+
+fn is_outside(
+    player_chunk: &ChunkBoundaries,
+    chunk: &ChunkBoundaries,
+    radius: &ChunkLoadingRadius,
+) -> bool {
+    let x = (chunk.min[0] - player_chunk.min[0]) / CHUNK_SIZE;
+    let y = (chunk.min[1] - player_chunk.min[1]) / CHUNK_SIZE;
+    let z = (chunk.min[2] - player_chunk.min[2]) / CHUNK_SIZE;
+    let (lowest, highest) = radius.vertical(player_chunk);
+    x.abs() > radius.unload || z.abs() > radius.unload || y < lowest - 1 || y > highest + 1
+}